@@ -1,7 +1,8 @@
-use crate::commands::Runnable;
+use crate::commands::{Broadcast, Runnable};
+use crate::util::broadcast::outln;
 
 /// List connected devices.
-#[derive(clap::Parser)]
+#[derive(clap::Parser, Clone)]
 pub struct Args {}
 
 impl Runnable for Args {
@@ -9,11 +10,13 @@ impl Runnable for Args {
 		let mut dev = dev.as_result()?;
 		let dev_info = dev.device_info()?;
 		let ext_dev_info = dev.extended_device_info()?;
-		println!("Device type: {}", dev_info.product);
-		println!("System version: {}", dev_info.version);
-		println!("CPU versions: {} {}", ext_dev_info.cpu0_version, ext_dev_info.cpu1_version);
-		println!("Touch version: {}", ext_dev_info.touch_version);
-		println!("System ID: {:08x}", ext_dev_info.system_id);
+		outln!("Device type: {}", dev_info.product);
+		outln!("System version: {}", dev_info.version);
+		outln!("CPU versions: {} {}", ext_dev_info.cpu0_version, ext_dev_info.cpu1_version);
+		outln!("Touch version: {}", ext_dev_info.touch_version);
+		outln!("System ID: {:08x}", ext_dev_info.system_id);
 		Ok(())
 	}
 }
+
+impl Broadcast for Args {}
@@ -1,15 +1,40 @@
 use crate::commands::Runnable;
+use crate::util::aliases;
 use v5_device::device;
 
 /// Print device info.
 #[derive(clap::Parser)]
-pub struct Args {}
+pub struct Args {
+	/// Also show the identifiers that can be passed to `--device`: USB serial numbers, system IDs, and aliases.
+	///
+	/// This briefly opens each device to read its system ID.
+	#[clap(long, short)]
+	long: bool,
+}
 
 impl Runnable for Args {
 	fn run(self, _dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
 		let devices = device::UploadableInfo::get_all()?;
+		let aliases = if self.long { aliases::load()? } else { Default::default() };
 		for device in devices.iter() {
-			println!("Device {} of type {}", device.name, device.device_type);
+			match device.user_port {
+				Some(ref user_port) => println!("Device {} of type {} (user port {})", device.name, device.device_type, user_port),
+				None => println!("Device {} of type {}", device.name, device.device_type),
+			}
+			if self.long {
+				println!("  USB serial: {}", device.serial_number.as_deref().unwrap_or("(unknown)"));
+				match device.read_system_id() {
+					Ok(system_id) => {
+						println!("  System ID: {:#010x}", system_id);
+						let mut names: Vec<&str> = aliases.iter().filter(|(_, &id)| id == system_id).map(|(alias, _)| alias.as_str()).collect();
+						names.sort_unstable();
+						if !names.is_empty() {
+							println!("  Aliases: {}", names.join(", "));
+						}
+					}
+					Err(err) => println!("  System ID: (could not be read: {})", err),
+				}
+			}
 		}
 		Ok(())
 	}
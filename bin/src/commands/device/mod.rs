@@ -1,9 +1,15 @@
-use crate::commands::Runnable;
+use crate::commands::{unsupported_broadcast, Broadcastable, Runnable};
+use crate::util::broadcast::Devices;
 use v5_device::util::presence::Presence;
 
 mod info;
 mod list;
+mod probe;
+mod raw;
 mod screen_capture;
+mod screen_mirror;
+mod screen_record;
+mod watch;
 
 #[derive(clap::Parser)]
 pub struct Args {
@@ -17,12 +23,23 @@ impl Runnable for Args {
 	}
 }
 
+impl Broadcastable for Args {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		self.sub.broadcast(devices)
+	}
+}
+
 /// Query device information.
 #[derive(clap::Subcommand)]
 enum Commands {
 	Info(info::Args),
 	List(list::Args),
+	Probe(probe::Args),
+	Raw(raw::Args),
 	ScreenCapture(screen_capture::Args),
+	ScreenMirror(screen_mirror::Args),
+	ScreenRecord(screen_record::Args),
+	Watch(watch::Args),
 }
 
 impl Runnable for Commands {
@@ -30,7 +47,21 @@ impl Runnable for Commands {
 		match self {
 			Commands::Info(args) => args.run(dev),
 			Commands::List(args) => args.run(dev),
+			Commands::Probe(args) => args.run(dev),
+			Commands::Raw(args) => args.run(dev),
 			Commands::ScreenCapture(args) => args.run(dev),
+			Commands::ScreenMirror(args) => args.run(dev),
+			Commands::ScreenRecord(args) => args.run(dev),
+			Commands::Watch(args) => args.run(dev),
+		}
+	}
+}
+
+impl Broadcastable for Commands {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		match self {
+			Commands::Info(args) => args.broadcast(devices),
+			_ => unsupported_broadcast(),
 		}
 	}
 }
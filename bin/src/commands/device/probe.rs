@@ -0,0 +1,73 @@
+use crate::commands::Runnable;
+use anyhow::Context;
+use clap_num::maybe_hex;
+use std::path::PathBuf;
+use std::time::Duration;
+use v5_device::device::{probe, CommandId};
+use v5_device::util::hex;
+
+/// Send every extended command in a range and report how the device responds to each. (Expert)
+///
+/// The report is written to a file named after the product and firmware version, like "brain-1.1.0-0.0.txt", so that reports from different versions can be compared with `diff`.
+/// If probing fails partway through, what was found is written to a ".partial.txt" file instead, leaving any complete report alone.
+///
+/// Commands are sent with the same payload, empty by default.
+/// Commands known to change the device's state, such as those that write, delete, or run files, are skipped unless `--include-state-changing` is given.
+/// Unknown commands are normally rejected because the payload is too short, but use `--skip` for anything you're unsure about.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// The first command ID to probe.
+	#[clap(long, parse(try_from_str=maybe_hex), default_value = "0")]
+	from: CommandId,
+	/// The last command ID to probe, inclusive.
+	#[clap(long, parse(try_from_str=maybe_hex), default_value = "0xff")]
+	to: CommandId,
+	/// A command ID not to probe. Can be specified multiple times.
+	#[clap(long, parse(try_from_str=maybe_hex))]
+	skip: Vec<CommandId>,
+	/// Also probe commands known to change the device's state. These could e.g. delete files or start a transfer that needs a power cycle to recover from.
+	#[clap(long)]
+	include_state_changing: bool,
+	/// The payload to send with each command, in hex.
+	#[clap(long, default_value = "")]
+	payload: String,
+	/// How long to wait for each response, in milliseconds.
+	#[clap(long, default_value = "1000")]
+	timeout: u64,
+	/// The directory to write the report to.
+	#[clap(long, default_value = ".")]
+	output_dir: PathBuf,
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let payload = hex::decode(&self.payload).context("Parsing payload")?;
+		let device_info = dev.device_info()?;
+		let mut report = probe::Report::new(device_info.product, device_info.version);
+		let skipped = |command_id: &CommandId| self.skip.contains(command_id) || (!self.include_state_changing && probe::STATE_CHANGING.contains(command_id));
+		dev.set_timeout(Duration::from_millis(self.timeout))?;
+		let mut failure = None;
+		for command_id in (self.from..=self.to).filter(|command_id| !skipped(command_id)) {
+			match dev.probe_ext_command(command_id, &payload) {
+				Ok(outcome) => {
+					println!("{:#04x} {}", command_id, outcome);
+					report.outcomes.insert(command_id, outcome);
+				}
+				Err(err) => {
+					failure = Some(anyhow::Error::from(err).context(format!("Probing command {:#04x}", command_id)));
+					break;
+				}
+			}
+		}
+		// keep what was found even if the link failed partway through
+		let restored = dev.reset_timeout();
+		let path = self.output_dir.join(report.file_name(failure.is_some()));
+		std::fs::write(&path, report.to_string()).with_context(|| format!("Writing report to {}", path.display()))?;
+		println!("Wrote {}report to {}", if failure.is_some() { "partial " } else { "" }, path.display());
+		match failure {
+			Some(err) => Err(err),
+			None => Ok(restored?),
+		}
+	}
+}
@@ -0,0 +1,49 @@
+use crate::commands::Runnable;
+use anyhow::Context;
+use clap_num::maybe_hex;
+use std::time::Duration;
+use v5_device::device::CommandId;
+use v5_device::util::hex;
+
+/// Send an arbitrary command and show the response as-is. (Expert)
+///
+/// NACKs and invalid CRCs are shown rather than treated as errors.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// The command ID.
+	#[clap(parse(try_from_str=maybe_hex))]
+	command: CommandId,
+	/// The payload, in hex. Whitespace and colons are ignored, so "01 02", "01:02", and "0102" are equivalent.
+	payload: Vec<String>,
+	/// Send a simple command rather than an extended command.
+	#[clap(long)]
+	simple: bool,
+	/// How long to wait for the response, in milliseconds.
+	#[clap(long)]
+	timeout: Option<u64>,
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let payload = hex::decode(&self.payload.join(" ")).context("Parsing payload")?;
+		if let Some(timeout) = self.timeout {
+			dev.set_timeout(Duration::from_millis(timeout))?;
+		}
+		let response = if self.simple { dev.raw_simple_command(self.command, &payload) } else { dev.raw_ext_command(self.command, &payload) };
+		dev.reset_timeout()?;
+		let response = response?;
+		match (response.response_byte, response.known_response_byte()) {
+			(Some(byte), Some(known)) => println!("Response byte: {:#04x} ({})", byte, known),
+			(Some(byte), None) => println!("Response byte: {:#04x} (unknown)", byte),
+			(None, _) => println!("Response byte: none"),
+		}
+		println!("Payload ({} bytes): {}", response.payload.len(), hex::encode(&response.payload));
+		match response.crc_valid {
+			Some(true) => println!("CRC: valid"),
+			Some(false) => println!("CRC: INVALID"),
+			None => println!("CRC: none"),
+		}
+		Ok(())
+	}
+}
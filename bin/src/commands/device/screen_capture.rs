@@ -1,66 +1,121 @@
 use crate::commands::Runnable;
+use crate::util::image::{self, Image, Tolerance};
 use anyhow::Context;
 use std::fs::File;
 use std::io::stdout;
-use std::io::{self, Write};
-use std::path::PathBuf;
-use v5_device::device::Device;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use v5_device::device::screen::{Framebuffer, Rect};
 
 /// Take a screen capture of the device.
+///
+/// With `--compare`, the capture is instead checked against a golden image, and the command fails if they differ by more than the tolerance.
+/// A diff image is written in that case, with the differing pixels in red.
 #[derive(clap::Parser)]
 pub struct Args {
-	/// Where to write the screen capture, in PNG format.
-	/// If not specified, or "-" is specified, output the data on standard output.
+	/// Where to write the screen capture.
+	/// If not specified, or "-" is specified, output the data on standard output, unless comparing.
 	output: Option<PathBuf>,
+	/// The output format. Inferred from the output's extension if not specified, or PNG if that doesn't help.
+	#[clap(long, arg_enum)]
+	format: Option<Format>,
+	/// Only capture this part of the screen, as X,Y,WIDTH,HEIGHT.
+	#[clap(long, conflicts_with = "uncropped")]
+	crop: Option<Rect>,
+	/// Include the columns of the framebuffer past the right edge of the screen, which are normally cropped off.
+	#[clap(long)]
+	uncropped: bool,
+	/// A PNG to compare the screen capture to.
+	#[clap(long)]
+	compare: Option<PathBuf>,
+	/// How many pixels may differ, as a count or a percentage of the compared pixels like "0.5%". Defaults to 0.
+	#[clap(long, requires = "compare")]
+	tolerance: Option<Tolerance>,
+	/// How much each color channel may differ before a pixel counts as different. Defaults to 0.
+	#[clap(long, requires = "compare")]
+	threshold: Option<u8>,
+	/// A region to leave out of the comparison, as X,Y,WIDTH,HEIGHT. Can be given multiple times.
+	#[clap(long = "mask", requires = "compare", multiple_occurrences = true)]
+	masks: Vec<Rect>,
+	/// Where to write the diff image if the comparison fails. Defaults to the golden image's path with a ".diff.png" extension.
+	#[clap(long, requires = "compare")]
+	diff: Option<PathBuf>,
 }
 
-impl Runnable for Args {
-	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
-		let mut dev = dev.as_result()?;
+#[derive(clap::ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	Png,
+	/// The framebuffer's raw pixels, 4 bytes each in BGRA order, including alpha.
+	Bgra,
+	/// Binary PPM (P6).
+	Ppm,
+	Bmp,
+}
 
-		let stream: Box<dyn Write> = match self.output {
-			Some(ref path) if path.as_os_str() != "-" => Box::new(File::create(path)?),
-			_ => Box::new(stdout()),
-		};
-		let mut stream = ScreenCapturePipeline::new(stream)?;
-		dev.capture_screen(&mut stream)?;
+impl Format {
+	fn infer(path: &Path) -> Option<Self> {
+		match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+			"png" => Some(Self::Png),
+			"bgra" | "raw" => Some(Self::Bgra),
+			"ppm" => Some(Self::Ppm),
+			"bmp" => Some(Self::Bmp),
+			_ => None,
+		}
+	}
+	fn write(self, framebuffer: &Framebuffer, region: Rect, output: impl Write) -> anyhow::Result<()> {
+		let mut output = BufWriter::new(output);
+		match self {
+			Self::Png => Image::from_framebuffer(framebuffer, region).write_png(&mut output)?,
+			Self::Bgra => output.write_all(&framebuffer.bgra_region(region))?,
+			Self::Ppm => Image::from_framebuffer(framebuffer, region).write_ppm(&mut output)?,
+			Self::Bmp => Image::from_framebuffer(framebuffer, region).write_bmp(&mut output)?,
+		}
+		output.flush()?;
 		Ok(())
 	}
 }
 
-/// The pipeline:
-///
-/// 0. Input a `Device::SCREEN_WIDTH` x `Device::SCREEN_HEIGHT` raw image blob in BGRA (due to endianness) format.
-/// 1. Crop its width to `Device::ACTUAL_SCREEN_WIDTH`.
-/// 2. Rearrange the data to RGB, dropping the alpha channel.
-/// 3. Output it as PNG data to the `Write`r provided at initialization.
-struct ScreenCapturePipeline {
-	output: png::StreamWriter<'static, Box<dyn Write>>,
-	current_x: usize,
-}
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
 
-impl ScreenCapturePipeline {
-	pub fn new(output: Box<dyn std::io::Write>) -> anyhow::Result<Self> {
-		let mut encoder = png::Encoder::new(output, Device::ACTUAL_SCREEN_WIDTH as u32, Device::SCREEN_HEIGHT as u32);
-		encoder.set_color(png::ColorType::Rgb);
-		encoder.set_depth(png::BitDepth::Eight);
-		let output = encoder.write_header().context("Writing PNG header")?.into_stream_writer().context("Converting PNG writer to stream writer")?;
-		Ok(Self { output, current_x: 0 })
-	}
-}
+		let region = match self.crop {
+			Some(crop) if !Framebuffer::FULL.encloses(&crop) => anyhow::bail!("The crop region {} is not within the {}x{} framebuffer", crop, Framebuffer::FULL.width, Framebuffer::FULL.height),
+			Some(crop) => crop,
+			None if self.uncropped => Framebuffer::FULL,
+			None => Framebuffer::VISIBLE,
+		};
 
-impl Write for ScreenCapturePipeline {
-	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
-		// The image is made up of u32 units, so we will never get a chunk less than 4 bytes as long as we always accept 4 bytes. Assert this.
-		assert!(data.len() >= 4);
-		if self.current_x < Device::ACTUAL_SCREEN_WIDTH {
-			// BGRA to RGB
-			self.output.write_all(&[data[2], data[1], data[0]])?;
+		let framebuffer = dev.capture_screen_image()?;
+		let format = self.format.or_else(|| self.output.as_deref().and_then(Format::infer)).unwrap_or(Format::Png);
+		match self.output {
+			Some(ref path) if path.as_os_str() != "-" => format.write(&framebuffer, region, File::create(path).with_context(|| format!("Creating {}", path.display()))?)?,
+			Some(_) => format.write(&framebuffer, region, stdout())?,
+			None if self.compare.is_none() => format.write(&framebuffer, region, stdout())?,
+			None => {}
 		}
-		self.current_x = (self.current_x + 1) % Device::SCREEN_WIDTH;
-		Ok(4)
-	}
-	fn flush(&mut self) -> io::Result<()> {
-		self.output.flush()
+
+		if let Some(ref golden_path) = self.compare {
+			let capture = Image::from_framebuffer(&framebuffer, region);
+			let golden = Image::read_png(BufReader::new(File::open(golden_path).with_context(|| format!("Opening {}", golden_path.display()))?)).with_context(|| format!("Reading {}", golden_path.display()))?;
+			if (golden.width, golden.height) != (capture.width, capture.height) {
+				anyhow::bail!("{} is {}x{}, but the screen capture is {}x{}", golden_path.display(), golden.width, golden.height, capture.width, capture.height);
+			}
+			let tolerance = self.tolerance.unwrap_or(Tolerance::Pixels(0));
+			let comparison = image::compare(&capture, &golden, &self.masks, self.threshold.unwrap_or(0));
+			let percent = 100.0 * comparison.differing as f64 / comparison.compared.max(1) as f64;
+			let summary = format!("{} of {} pixels differ ({:.3}%)", comparison.differing, comparison.compared, percent);
+			if tolerance.allows(comparison.differing, comparison.compared) {
+				// keep stdout clean in case the capture itself was written there
+				eprintln!("Screen matches {}: {}", golden_path.display(), summary);
+			} else {
+				let diff_path = self.diff.clone().unwrap_or_else(|| golden_path.with_extension("diff.png"));
+				let mut diff_file = File::create(&diff_path).with_context(|| format!("Creating {}", diff_path.display()))?;
+				comparison.diff.write_png(&mut diff_file)?;
+				diff_file.flush()?;
+				anyhow::bail!("Screen does not match {}: {}, more than the tolerance of {}. Diff written to {}", golden_path.display(), summary, tolerance, diff_path.display());
+			}
+		}
+		Ok(())
 	}
 }
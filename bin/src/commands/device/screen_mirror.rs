@@ -0,0 +1,114 @@
+use crate::commands::program::terminal;
+use crate::commands::Runnable;
+use crate::util::image::Image;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::time::{Duration, Instant};
+use v5_device::device::screen::Framebuffer;
+use v5_device::device::Device;
+
+/// Show the device's screen in the terminal, updating it continuously.
+///
+/// Each character shows two pixels using a half block and 24-bit color, which most terminals support.
+/// The image is scaled down to fit the terminal; enlarge it or reduce the font size for more detail.
+/// Press Enter to stop.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// How long to wait between the start of each capture, in milliseconds.
+	#[clap(long, default_value = "100")]
+	interval: u64,
+	/// The width of the image in characters. Defaults to as large as fits in the terminal.
+	#[clap(long)]
+	width: Option<usize>,
+}
+
+/// The size of the terminal in characters, leaving a line for the status.
+fn available_size() -> (usize, usize) {
+	match terminal_size::terminal_size() {
+		Some((terminal_size::Width(width), terminal_size::Height(height))) => (width.into(), usize::from(height).saturating_sub(1).max(1)),
+		None => (80, 23),
+	}
+}
+
+/// The size of the image in pixels, keeping the screen's aspect ratio. The height is even, since each character is two pixels tall.
+fn image_size(columns: usize, rows: usize) -> (usize, usize) {
+	let width = columns.min(Device::ACTUAL_SCREEN_WIDTH).min(rows * 2 * Device::ACTUAL_SCREEN_WIDTH / Device::SCREEN_HEIGHT).max(1);
+	let height = (width * Device::SCREEN_HEIGHT / Device::ACTUAL_SCREEN_WIDTH / 2 * 2).max(2);
+	(width, height)
+}
+
+/// Render each pair of pixel rows as a line of upper half blocks, with the foreground as the top pixel and the background as the bottom one.
+fn render(image: &Image) -> Vec<String> {
+	(0..image.height / 2)
+		.map(|row| {
+			let mut line = String::new();
+			let mut last = None;
+			for x in 0..image.width {
+				let colors = (image.pixel(x, row * 2), image.pixel(x, row * 2 + 1));
+				if last != Some(colors) {
+					let ([tr, tg, tb], [br, bg, bb]) = colors;
+					write!(line, "\x1b[38;2;{};{};{};48;2;{};{};{}m", tr, tg, tb, br, bg, bb).unwrap();
+					last = Some(colors);
+				}
+				line.push('▀');
+			}
+			// reset at the end of every line so that nothing is left colored if interrupted
+			line.push_str("\x1b[0m");
+			line
+		})
+		.collect()
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let interval = Duration::from_millis(self.interval);
+		let stdin = terminal::read_stdin_in_background();
+		let mut stdout = std::io::stdout();
+
+		let mut shown: Vec<String> = Vec::new();
+		let mut size = (0, 0);
+		// for the frame rate, averaged over the last second or so
+		let mut frame_times = std::collections::VecDeque::new();
+		let mut next_frame = Instant::now();
+		while stdin.try_recv().is_err() {
+			let capture = Image::from_framebuffer(&dev.capture_screen_image()?, Framebuffer::VISIBLE);
+
+			let (columns, rows) = available_size();
+			let new_size = image_size(self.width.unwrap_or(columns), rows);
+			let mut output = String::new();
+			if new_size != size {
+				// the terminal was resized, so start afresh
+				size = new_size;
+				shown.clear();
+				output.push_str("\x1b[2J");
+			}
+			let lines = render(&capture.resize(size.0, size.1));
+			for (row, line) in lines.iter().enumerate() {
+				if shown.get(row) != Some(line) {
+					write!(output, "\x1b[{};1H{}", row + 1, line).unwrap();
+				}
+			}
+			shown = lines;
+
+			let now = Instant::now();
+			frame_times.push_back(now);
+			while frame_times.len() > 2 && now - frame_times[0] > Duration::from_secs(1) {
+				frame_times.pop_front();
+			}
+			let fps = (frame_times.len() - 1) as f64 / (now - frame_times[0]).as_secs_f64().max(f64::EPSILON);
+			write!(output, "\x1b[{};1H\x1b[2K{:.1} frames per second, press Enter to stop", shown.len() + 1, fps).unwrap();
+			stdout.write_all(output.as_bytes())?;
+			stdout.flush()?;
+
+			// aim for a steady rate, without trying to catch up after falling behind
+			next_frame += interval;
+			match next_frame.checked_duration_since(Instant::now()) {
+				Some(remaining) => std::thread::sleep(remaining),
+				None => next_frame = Instant::now(),
+			}
+		}
+		println!();
+		Ok(())
+	}
+}
@@ -0,0 +1,190 @@
+use crate::commands::program::terminal;
+use crate::commands::Runnable;
+use crate::util::image::Image;
+use anyhow::Context;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use v5_device::device::screen::Framebuffer;
+use v5_device::device::Device;
+
+/// Record the device's screen by taking screen captures repeatedly.
+///
+/// Recording stops after the duration or number of frames given, or when Enter is pressed.
+/// Consecutive identical captures are merged into one longer frame in animations, so a mostly static screen takes up little space.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// Where to write the recording. For numbered frames, this is a directory.
+	output: PathBuf,
+	/// The output format. Inferred from the output's extension if not specified: ".gif" for GIF, ".png" or ".apng" for APNG, and numbered frames otherwise.
+	#[clap(long, arg_enum)]
+	format: Option<Format>,
+	/// How long to wait between the start of each capture, in milliseconds.
+	/// A capture takes a while, so the frame rate may be lower than this allows.
+	#[clap(long, default_value = "200")]
+	interval: u64,
+	/// Stop after this many seconds.
+	#[clap(long)]
+	duration: Option<f64>,
+	/// Stop after this many captures.
+	#[clap(long)]
+	frames: Option<usize>,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy, PartialEq, Eq)]
+enum Format {
+	/// Animated PNG. Frames are kept in memory until the recording ends.
+	Apng,
+	/// Animated GIF, reduced to 256 colors per frame.
+	Gif,
+	/// Numbered PNG files, one per capture.
+	Frames,
+}
+
+impl Format {
+	fn infer(path: &Path) -> Self {
+		match path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase).as_deref() {
+			Some("gif") => Self::Gif,
+			Some("png" | "apng") => Self::Apng,
+			_ => Self::Frames,
+		}
+	}
+}
+
+/// Somewhere to put the frames of a recording.
+trait Sink {
+	/// Add a frame that is shown for `delay`.
+	fn add(&mut self, frame: &Image, delay: Duration) -> anyhow::Result<()>;
+	fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+struct Apng {
+	path: PathBuf,
+	frames: Vec<(Image, Duration)>,
+}
+
+impl Sink for Apng {
+	fn add(&mut self, frame: &Image, delay: Duration) -> anyhow::Result<()> {
+		self.frames.push((frame.clone(), delay));
+		Ok(())
+	}
+	fn finish(self: Box<Self>) -> anyhow::Result<()> {
+		let mut output = BufWriter::new(File::create(&self.path).with_context(|| format!("Creating {}", self.path.display()))?);
+		let mut encoder = png::Encoder::new(&mut output, Device::ACTUAL_SCREEN_WIDTH as u32, Device::SCREEN_HEIGHT as u32);
+		encoder.set_color(png::ColorType::Rgb);
+		encoder.set_depth(png::BitDepth::Eight);
+		encoder.set_animated(self.frames.len() as u32, 0).context("Writing APNG header")?;
+		let mut writer = encoder.write_header().context("Writing APNG header")?;
+		for (frame, delay) in self.frames.iter() {
+			writer.set_frame_delay(delay.as_millis().min(u16::MAX.into()) as u16, 1000).context("Writing APNG frame")?;
+			writer.write_image_data(&frame.data).context("Writing APNG frame")?;
+		}
+		writer.finish().context("Finishing APNG")?;
+		// `finish` doesn't drop its output, so it must be flushed here
+		output.flush()?;
+		Ok(())
+	}
+}
+
+struct Gif {
+	encoder: gif::Encoder<BufWriter<File>>,
+}
+
+impl Gif {
+	fn create(path: &Path) -> anyhow::Result<Self> {
+		let output = BufWriter::new(File::create(path).with_context(|| format!("Creating {}", path.display()))?);
+		let mut encoder = gif::Encoder::new(output, Device::ACTUAL_SCREEN_WIDTH as u16, Device::SCREEN_HEIGHT as u16, &[]).context("Writing GIF header")?;
+		encoder.set_repeat(gif::Repeat::Infinite).context("Writing GIF header")?;
+		Ok(Self { encoder })
+	}
+}
+
+impl Sink for Gif {
+	fn add(&mut self, frame: &Image, delay: Duration) -> anyhow::Result<()> {
+		let mut gif_frame = gif::Frame::from_rgb_speed(frame.width as u16, frame.height as u16, &frame.data, 10);
+		// in hundredths of a second
+		gif_frame.delay = (delay.as_millis() / 10).min(u16::MAX.into()) as u16;
+		self.encoder.write_frame(&gif_frame).context("Writing GIF frame")?;
+		Ok(())
+	}
+	fn finish(self: Box<Self>) -> anyhow::Result<()> {
+		self.encoder.into_inner().context("Finishing GIF")?.flush()?;
+		Ok(())
+	}
+}
+
+struct Frames {
+	directory: PathBuf,
+	count: usize,
+}
+
+impl Sink for Frames {
+	fn add(&mut self, frame: &Image, _delay: Duration) -> anyhow::Result<()> {
+		let path = self.directory.join(format!("frame_{:05}.png", self.count));
+		frame.write_png(BufWriter::new(File::create(&path).with_context(|| format!("Creating {}", path.display()))?))?;
+		self.count += 1;
+		Ok(())
+	}
+	fn finish(self: Box<Self>) -> anyhow::Result<()> {
+		Ok(())
+	}
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let format = self.format.unwrap_or_else(|| Format::infer(&self.output));
+		let mut sink: Box<dyn Sink> = match format {
+			Format::Apng => Box::new(Apng { path: self.output.clone(), frames: Vec::new() }),
+			Format::Gif => Box::new(Gif::create(&self.output)?),
+			Format::Frames => {
+				fs::create_dir_all(&self.output).with_context(|| format!("Creating {}", self.output.display()))?;
+				Box::new(Frames { directory: self.output.clone(), count: 0 })
+			}
+		};
+
+		let interval = Duration::from_millis(self.interval);
+		let duration = self.duration.map(Duration::from_secs_f64);
+		let stdin = terminal::read_stdin_in_background();
+		eprintln!("Recording, press Enter to stop");
+
+		let start = Instant::now();
+		let mut num_captures = 0;
+		// the latest distinct frame, not yet added since it's unknown how long it's shown for
+		let mut pending: Option<(Image, Instant)> = None;
+		loop {
+			let capture_start = Instant::now();
+			let frame = Image::from_framebuffer(&dev.capture_screen_image()?, Framebuffer::VISIBLE);
+			num_captures += 1;
+			match pending {
+				Some((ref previous, _)) if format != Format::Frames && *previous == frame => {}
+				_ => {
+					if let Some((previous, shown_at)) = pending.take() {
+						sink.add(&previous, capture_start - shown_at)?;
+					}
+					pending = Some((frame, capture_start));
+				}
+			}
+
+			// closed standard input doesn't count, so that recording works without a terminal
+			let stop_requested = stdin.try_recv().is_ok();
+			if stop_requested || self.frames.is_some_and(|frames| num_captures >= frames) || duration.is_some_and(|duration| start.elapsed() >= duration) {
+				break;
+			}
+			if let Some(remaining) = interval.checked_sub(capture_start.elapsed()) {
+				std::thread::sleep(remaining);
+			}
+		}
+		let end = Instant::now();
+		if let Some((frame, shown_at)) = pending {
+			// the last frame gets a full interval, or it would hardly be visible
+			sink.add(&frame, (end - shown_at).max(interval))?;
+		}
+		sink.finish()?;
+
+		let elapsed = (end - start).as_secs_f64();
+		eprintln!("Recorded {} captures in {:.1} s ({:.2} frames per second) to {}", num_captures, elapsed, num_captures as f64 / elapsed, self.output.display());
+		Ok(())
+	}
+}
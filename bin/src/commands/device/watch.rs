@@ -0,0 +1,95 @@
+use crate::commands::Runnable;
+use crate::util::{aliases, shell};
+use anyhow::Context;
+use log::warn;
+use std::time::Duration;
+use v5_device::device::discover::{DeviceSelector, UploadableInfo, WatchEvent, Watcher};
+use v5_device::device::helpers::SystemId;
+
+/// Print devices as they are plugged in and unplugged, optionally running a command for each.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// A shell command to run when a brain or controller is plugged in, e.g. `reveng --device "$REVENG_DEVICE" program upload`.
+	///
+	/// The environment variable REVENG_DEVICE is set to the path of the device, and REVENG_SYSTEM_ID to its system ID.
+	#[clap(long)]
+	hook: Option<String>,
+	/// Only run the hook for this device, by system ID or alias. Can be specified multiple times.
+	#[clap(long = "only", requires = "hook")]
+	only: Vec<DeviceSelector>,
+	/// How often to check for changes, in milliseconds.
+	#[clap(long, default_value = "500")]
+	interval: u64,
+}
+
+impl Args {
+	fn wanted_ids(&self) -> anyhow::Result<Option<Vec<SystemId>>> {
+		if self.only.is_empty() {
+			return Ok(None);
+		}
+		let aliases = aliases::load()?;
+		self.only
+			.iter()
+			.map(|selector| match selector {
+				DeviceSelector::SystemId(system_id) => Ok(*system_id),
+				DeviceSelector::Alias(alias) => aliases.get(alias).copied().with_context(|| format!("Unknown device alias {}", alias)),
+				other => anyhow::bail!("{} is not a system ID or alias", other),
+			})
+			.collect::<anyhow::Result<_>>()
+			.map(Some)
+	}
+}
+
+impl Runnable for Args {
+	fn run(self, _dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let wanted_ids = self.wanted_ids()?;
+		let mut watcher = Watcher::new(Duration::from_millis(self.interval));
+		loop {
+			for event in watcher.wait().context("Listing serial ports")? {
+				println!("{}", event);
+				let (info, hook) = match (&event, &self.hook) {
+					(WatchEvent::Attached(port), Some(hook)) => match port.uploadable() {
+						Some(info) => (info, hook),
+						None => continue,
+					},
+					_ => continue,
+				};
+				let system_id = match read_system_id(&info) {
+					Ok(system_id) => system_id,
+					Err(err) => {
+						warn!("Could not read the system ID of {}, so not running the hook: {}", info.name, err);
+						continue;
+					}
+				};
+				println!("  System ID: {:#010x}", system_id);
+				if wanted_ids.as_ref().is_none_or(|ids| ids.contains(&system_id)) {
+					run_hook(hook, &info.name, system_id);
+				}
+			}
+		}
+	}
+}
+
+/// A device that was just plugged in can take a moment to start responding.
+fn read_system_id(info: &UploadableInfo) -> v5_device::device::Result<SystemId> {
+	const ATTEMPTS: usize = 3;
+	let mut ret = info.read_system_id();
+	for _ in 1..ATTEMPTS {
+		if ret.is_ok() {
+			break;
+		}
+		std::thread::sleep(Duration::from_millis(500));
+		ret = info.read_system_id();
+	}
+	ret
+}
+
+/// Failures are reported, but don't stop the watch.
+fn run_hook(hook: &str, device: &str, system_id: SystemId) {
+	let status = shell::command(hook).env("REVENG_DEVICE", device).env("REVENG_SYSTEM_ID", format!("{:#010x}", system_id)).status();
+	match status {
+		Ok(status) if status.success() => println!("  Hook succeeded"),
+		Ok(status) => warn!("Hook failed for {}: {}", device, status),
+		Err(err) => warn!("Could not run hook for {}: {}", device, err),
+	}
+}
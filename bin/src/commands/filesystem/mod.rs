@@ -1,4 +1,5 @@
-use crate::commands::Runnable;
+use crate::commands::{unsupported_broadcast, Broadcastable, Runnable};
+use crate::util::broadcast::Devices;
 use v5_device::util::presence::Presence;
 
 mod cat;
@@ -22,6 +23,12 @@ impl Runnable for Args {
 	}
 }
 
+impl Broadcastable for Args {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		self.sub.broadcast(devices)
+	}
+}
+
 /// Interact with the filesystem.
 #[derive(clap::Subcommand)]
 enum Commands {
@@ -49,3 +56,12 @@ impl Runnable for Commands {
 		}
 	}
 }
+
+impl Broadcastable for Commands {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		match self {
+			Commands::Sponge(args) => args.broadcast(devices),
+			_ => unsupported_broadcast(),
+		}
+	}
+}
@@ -1,13 +1,16 @@
-use crate::commands::Runnable;
+use crate::commands::{Broadcast, Runnable};
+use crate::util;
 use anyhow::Context;
 use clap_num::maybe_hex;
 use std::io::{stdin, Read};
 use v5_device::device::filesystem as fs;
+use v5_device::program::elf;
 
 /// Write stdin to a remote file.
 ///
 /// To "push" a file to the device, you can add ` < local.file` to the command line.
-#[derive(clap::Parser)]
+/// An ELF is converted to the flat image that the brain loads, and has to be linked for the address it's placed at.
+#[derive(clap::Parser, Clone)]
 pub struct Args {
 	/// Remote file.
 	file: fs::QualFile,
@@ -18,7 +21,7 @@ pub struct Args {
 	///
 	/// Only really matters for executables.
 	/// If not specified and the remote file exists, use its address.
-	/// Otherwise, use a predefined address.
+	/// Otherwise, or if the file is an ELF, use a predefined address.
 	#[clap(long, parse(try_from_str=maybe_hex))]
 	address: Option<fs::Address>,
 	/// The link of the file. (Expert)
@@ -26,20 +29,63 @@ pub struct Args {
 	/// If file A has a link to file B, then B is loaded into memory along with A when A is executed.
 	#[clap(long)]
 	link: Option<fs::QualFileName>,
+	/// Gzip the file before sending it, which makes uploads over the controller's radio faster.
+	///
+	/// VEXos only decompresses executables, so this is refused for files whose type isn't "bin".
+	#[clap(long, short = 'z')]
+	compress: bool,
+	/// Standard input, if it was read ahead of time.
+	#[clap(skip)]
+	data: Option<Vec<u8>>,
 }
 
 impl Runnable for Args {
 	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		self.check()?;
 		let mut dev = dev.as_result()?;
-		let mut data = Vec::default();
-		// we have to buffer this to have the size and the CRC
-		stdin().read_to_end(&mut data).context("Could not read from stdin")?;
+		let data = match self.data {
+			Some(data) => data,
+			None => read_stdin()?,
+		};
+		let (data, address) = if elf::is_elf(&data) {
+			let address = self.address.unwrap_or(fs::DEFAULT_ADDRESS);
+			(util::elf::flatten(data, address)?, Some(address))
+		} else {
+			(data, self.address)
+		};
 		let args = fs::WriteArgs {
-			address: self.address,
+			address,
 			overwrite: self.overwrite,
 			linked_file: self.link,
+			compress: self.compress,
 			..Default::default()
 		};
 		dev.write_file_from_slice(&data, &self.file, &args).context("Writing file")
 	}
 }
+
+impl Broadcast for Args {
+	/// Standard input can only be read once, so read it for all devices.
+	fn prepare(&mut self) -> anyhow::Result<()> {
+		self.check()?;
+		self.data = Some(read_stdin()?);
+		Ok(())
+	}
+}
+
+impl Args {
+	/// Refuse to compress anything but executables, since the device would keep other files compressed.
+	fn check(&self) -> anyhow::Result<()> {
+		if self.compress && self.file.ty.as_str() != Ok("bin") {
+			anyhow::bail!("Only executables (files of type \"bin\") can be compressed, since VEXos wouldn't decompress {}", self.file);
+		}
+		Ok(())
+	}
+}
+
+fn read_stdin() -> anyhow::Result<Vec<u8>> {
+	let mut data = Vec::default();
+	// we have to buffer this to have the size and the CRC
+	stdin().read_to_end(&mut data).context("Could not read from stdin")?;
+	Ok(data)
+}
@@ -1,26 +1,70 @@
 //! The user interface on the command line.
 
 use crate::logging;
+use crate::util::{aliases, broadcast};
 use anyhow::Context;
 use clap::Parser;
-use std::path::PathBuf;
-use v5_device::device::{Device, UploadableInfo};
-use v5_device::util::presence::Presence;
+use lazy_static::lazy_static;
+use log::warn;
+use std::fs::File;
+use std::io::{BufReader, LineWriter};
+use std::path::{Path, PathBuf};
+use v5_device::device::discover::DeviceSelector;
+use v5_device::device::{Device, RetryPolicy, UploadableInfo, UploadableType};
+use v5_device::emulator::VirtualBrain;
+use v5_device::transport::capture::{self, Recorder, Replay};
+use v5_device::util::presence::{NotOne, Presence};
 
 mod device;
 mod filesystem;
 mod program;
+mod protocol;
+mod test_run;
+
+lazy_static! {
+	/// For the `--retries` help text, which needs a string.
+	static ref DEFAULT_RETRIES: String = RetryPolicy::DEFAULT.max_retries.to_string();
+}
 
 /// A command that can be run with an arbitrary number of devices present (none, one, or many).
 trait Runnable {
 	fn run(self, device: Presence) -> anyhow::Result<()>;
 }
 
+/// A command that can be run on every device at once, with `--all-devices`. It is run with `Presence::One` for each device.
+trait Broadcast: Runnable + Clone + Send + Sync {
+	/// Anything that has to be done once rather than once per device, such as reading standard input.
+	fn prepare(&mut self) -> anyhow::Result<()> {
+		Ok(())
+	}
+}
+
+/// A command that might support `--all-devices`, depending on the subcommand.
+trait Broadcastable {
+	fn broadcast(self, devices: broadcast::Devices) -> anyhow::Result<()>;
+}
+
+impl<T: Broadcast> Broadcastable for T {
+	fn broadcast(mut self, devices: broadcast::Devices) -> anyhow::Result<()> {
+		if devices.is_empty() {
+			anyhow::bail!(NotOne::None);
+		}
+		self.prepare()?;
+		broadcast::run_on_all(devices, |device| self.clone().run(Presence::One(device)))
+	}
+}
+
+fn unsupported_broadcast() -> anyhow::Result<()> {
+	anyhow::bail!("This command does not support `--all-devices`.")
+}
+
 #[derive(clap::Subcommand)]
 enum Subcommand {
 	Filesystem(filesystem::Args),
 	Program(program::Args),
 	Device(device::Args),
+	Protocol(protocol::Args),
+	TestRun(test_run::Args),
 }
 
 impl Runnable for Subcommand {
@@ -29,6 +73,19 @@ impl Runnable for Subcommand {
 			Subcommand::Filesystem(args) => args.run(dev),
 			Subcommand::Program(args) => args.run(dev),
 			Subcommand::Device(args) => args.run(dev),
+			Subcommand::Protocol(args) => args.run(dev),
+			Subcommand::TestRun(args) => args.run(dev),
+		}
+	}
+}
+
+impl Broadcastable for Subcommand {
+	fn broadcast(self, devices: broadcast::Devices) -> anyhow::Result<()> {
+		match self {
+			Subcommand::Filesystem(args) => args.broadcast(devices),
+			Subcommand::Program(args) => args.broadcast(devices),
+			Subcommand::Device(args) => args.broadcast(devices),
+			Subcommand::Protocol(_) | Subcommand::TestRun(_) => unsupported_broadcast(),
 		}
 	}
 }
@@ -39,12 +96,38 @@ struct Args {
 	/// Increase verbosity.
 	#[clap(long = "verbose", short, parse(from_occurrences))]
 	verbosity: usize,
-	/// Specify the path to the device
-	#[cfg_attr(target_family = "unix", doc = "e.g., /dev/ttyACM0.")]
-	#[cfg_attr(target_family = "windows", doc = "e.g., COM1.")]
-	/// Not necessary if there is only one device.
+	/// Specify the device. Not necessary if there is only one device.
+	///
+	/// This can be the path to the device
+	#[cfg_attr(target_family = "unix", doc = "(e.g., /dev/ttyACM0),")]
+	#[cfg_attr(target_family = "windows", doc = "(e.g., COM1),")]
+	/// its system ID (e.g., 0x1234abcd), its USB serial number prefixed with "serial:", or an alias.
+	/// Aliases are read from the file in the `REVENG_ALIASES` environment variable, or by default reveng/aliases.ini in the user configuration directory, with lines such as `robot-a = 0x1234abcd`.
+	/// `device list --long` shows these identifiers.
 	#[clap(long = "device", short)]
-	device_path: Option<PathBuf>,
+	device: Option<DeviceSelector>,
+	/// Use an emulated brain instead of a real device. (Testing)
+	///
+	/// The emulated brain starts out empty and nothing is kept after the command finishes.
+	#[clap(long, conflicts_with = "device")]
+	virtual_device: bool,
+	/// Run the command on every connected device in parallel.
+	///
+	/// Only some commands support this, such as `program upload`, `program remove`, `filesystem sponge`, and `device info`.
+	/// Output is prefixed with the device it came from. The command fails if it failed on any device, or if any device couldn't be opened.
+	#[clap(long, conflicts_with_all = &["device", "virtual-device"])]
+	all_devices: bool,
+	/// Record all traffic with the device to a capture file.
+	#[clap(long, conflicts_with = "all-devices")]
+	capture: Option<PathBuf>,
+	/// Instead of communicating with a device, play back a capture file recorded with `--capture`. (Testing)
+	#[clap(long, conflicts_with_all = &["device", "virtual-device", "all-devices"])]
+	replay: Option<PathBuf>,
+	/// How many times to resend a command whose response was garbled or missing.
+	///
+	/// Only commands that are safe to repeat are retried, such as queries and file transfer packets.
+	#[clap(long, default_value = &DEFAULT_RETRIES)]
+	retries: u32,
 	#[clap(subcommand)]
 	sub: Subcommand,
 }
@@ -54,12 +137,63 @@ impl Args {
 		if self.verbosity > 0 {
 			logging::set_from_int(self.verbosity);
 		}
-		let device = if let Some(ref device_path) = self.device_path {
-			Presence::One(Device::try_from(device_path.as_ref()).context("Invalid device provided")?)
+		// devices that were found but couldn't be opened, e.g. because they're busy
+		let mut unopened = Vec::new();
+		let device = if self.virtual_device {
+			Presence::One(VirtualBrain::default().connect().context("Connecting to emulated brain")?)
+		} else if let Some(ref replay_path) = self.replay {
+			let capture = capture::read_capture(&mut BufReader::new(File::open(replay_path).context("Opening capture to replay")?)).context("Reading capture to replay")?;
+			// captures from before the header was added were all of brains
+			let device_type = capture.device_type.unwrap_or(UploadableType::Brain);
+			Presence::One(Device::from_transport(device_type, Box::new(Replay::new(capture.events))).context("Replaying capture")?)
+		} else if let Some(ref selector) = self.device {
+			let aliases = match selector {
+				DeviceSelector::Alias(_) => aliases::load()?,
+				_ => Default::default(),
+			};
+			Presence::One(Device::try_from(selector.select(&aliases).context("Invalid device provided")?).context("Opening device")?)
 		} else {
-			Presence::from(UploadableInfo::get_all().context("Failed to get serial ports")?.into_iter().filter_map(|port| Device::try_from(port).ok()).collect::<Vec<Device>>())
+			let mut opened = Vec::new();
+			for port in UploadableInfo::get_all().context("Failed to get serial ports")? {
+				let name = port.name.clone();
+				match Device::try_from(port) {
+					Ok(device) => opened.push(device),
+					Err(err) => unopened.push((name, anyhow::Error::from(err).context("Opening device"))),
+				}
+			}
+			Presence::from(opened)
+		};
+		let mut device = match self.capture {
+			Some(ref capture_path) => capture_device(device, capture_path)?,
+			None => device,
 		};
-		self.sub.run(device)
+		for device in device.devices_mut() {
+			device.set_retry_policy(RetryPolicy {
+				max_retries: self.retries,
+				..Default::default()
+			});
+		}
+		if self.all_devices {
+			self.sub.broadcast(broadcast::Devices { opened: device.into_all(), unopened })
+		} else {
+			for (port, err) in unopened {
+				warn!("Skipping {}: {:#}", port, err);
+			}
+			self.sub.run(device)
+		}
+	}
+}
+
+/// Wrap the device's transport so that its traffic is recorded.
+fn capture_device(device: Presence, path: &Path) -> anyhow::Result<Presence> {
+	match device {
+		Presence::One(device) => {
+			let capture = LineWriter::new(File::create(path).context("Creating capture file")?);
+			let (ty, transport) = device.into_transport();
+			Ok(Presence::One(Device::from_transport(ty, Box::new(Recorder::new(transport, Box::new(capture), ty))).context("Starting capture")?))
+		}
+		Presence::Many(_) => anyhow::bail!("Only one device can be captured at a time. You can specify the device with `--device`."),
+		Presence::None => Ok(Presence::None),
 	}
 }
 
@@ -0,0 +1,125 @@
+use super::{symbolize, terminal};
+use crate::commands::Runnable;
+use crate::util::rotating_file::RotatingFile;
+use anyhow::Context;
+use log::warn;
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{LineWriter, Write};
+use std::path::PathBuf;
+use v5_device::program::stream::{Decoder, Lines};
+use v5_device::program::telemetry::{Sample, SampleFromStrError};
+
+/// Record everything the running program prints to a file, with the time each line was received.
+///
+/// Each line of the log is the time, the stream (such as `sout` or `serr`, or `-` for output that isn't in PROS's format), and the line.
+/// Runs until interrupted with Ctrl-C, until the program stops, or until the user port goes away.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// The log file. It is appended to if it already exists.
+	#[clap(long, short, default_value = "program.log")]
+	output: PathBuf,
+	/// Once the log file would grow past this many bytes, it is renamed with the suffix `.1` and a new one is started.
+	#[clap(long, default_value = "1048576")]
+	max_size: u64,
+	/// How many old log files to keep, as `.1`, `.2`, and so on.
+	#[clap(long, default_value = "5")]
+	keep: usize,
+	/// Also write telemetry lines, such as `@tlm left=1.2 right=3.4`, to this CSV file.
+	///
+	/// There is a column for the time and for each value. The columns are taken from the first telemetry line; values that weren't in it are left out.
+	#[clap(long)]
+	csv: Option<PathBuf>,
+	/// The tag of the telemetry lines to write to the CSV file, i.e. the word after the `@`.
+	#[clap(long, default_value = "tlm", requires = "csv")]
+	tag: String,
+	/// Don't show the program's output on the terminal as well.
+	#[clap(long, short)]
+	quiet: bool,
+	#[clap(flatten)]
+	symbolize: symbolize::Args,
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let mut symbols = self.symbolize.load()?;
+		let mut log = RotatingFile::open(&self.output, self.max_size, self.keep).context("Opening log file")?;
+		let mut csv = self.csv.as_ref().map(|path| File::create(path).map(|file| Csv::new(LineWriter::new(file)))).transpose().context("Creating CSV file")?;
+		let mut decoder = Decoder::new();
+		let mut lines = Lines::new();
+		terminal::read_user_port(&mut dev, false, |data| {
+			for frame in decoder.push(data) {
+				if !self.quiet {
+					terminal::show(&frame)?;
+				}
+				for (stream, line) in lines.push(&frame) {
+					let time = chrono::Local::now().format("%Y-%m-%dT%H:%M:%S%.3f").to_string();
+					let stream = stream.map(|stream| stream.to_string()).unwrap_or_else(|| "-".to_owned());
+					log.write_line(&format!("{} {} {}", time, stream, line)).context("Writing to log file")?;
+					for annotation in symbols.annotate(&line) {
+						if !self.quiet {
+							eprintln!("{}", annotation);
+						}
+						log.write_line(&format!("{} {} {}", time, stream, annotation)).context("Writing to log file")?;
+					}
+					if let Some(ref mut csv) = csv {
+						match line.parse::<Sample>() {
+							Ok(sample) if sample.tag == self.tag => csv.write(&time, &sample).context("Writing to CSV file")?,
+							Ok(_) | Err(SampleFromStrError::NotASample) => {}
+							Err(err) => warn!("Ignoring telemetry line {:?}: {}", line, err),
+						}
+					}
+				}
+			}
+			Ok(())
+		})
+	}
+}
+
+/// Telemetry samples as CSV, with columns decided by the first sample.
+struct Csv<W: Write> {
+	writer: W,
+	columns: Option<Vec<String>>,
+	/// Values that aren't in any column and have already been warned about.
+	ignored: HashSet<String>,
+}
+
+impl<W: Write> Csv<W> {
+	fn new(writer: W) -> Self {
+		Self {
+			writer,
+			columns: None,
+			ignored: HashSet::new(),
+		}
+	}
+	fn write(&mut self, time: &str, sample: &Sample) -> std::io::Result<()> {
+		let columns = match self.columns {
+			Some(ref columns) => columns,
+			None => {
+				let columns = sample.values.iter().map(|(name, _)| name.clone()).collect::<Vec<_>>();
+				let header = std::iter::once("time").chain(columns.iter().map(String::as_str)).map(csv_field).collect::<Vec<_>>();
+				writeln!(self.writer, "{}", header.join(","))?;
+				self.columns.insert(columns)
+			}
+		};
+		for (name, _) in sample.values.iter() {
+			if !columns.contains(name) && self.ignored.insert(name.clone()) {
+				warn!("Telemetry value {} was not in the first sample, so it is left out of the CSV file", name);
+			}
+		}
+		let row = std::iter::once(time.to_owned())
+			.chain(columns.iter().map(|name| sample.get(name).map(|value| value.to_string()).unwrap_or_default()))
+			.collect::<Vec<_>>();
+		writeln!(self.writer, "{}", row.join(","))
+	}
+}
+
+/// Quote the field if it has characters that mean something in CSV.
+fn csv_field(field: &str) -> String {
+	if field.contains([',', '"']) {
+		format!("\"{}\"", field.replace('"', "\"\""))
+	} else {
+		field.to_owned()
+	}
+}
@@ -1,12 +1,17 @@
-use crate::commands::Runnable;
+use crate::commands::{unsupported_broadcast, Broadcastable, Runnable};
+use crate::util::broadcast::Devices;
 use v5_device::util::presence::Presence;
 
 mod info;
 mod list;
+mod log;
 mod remove;
 mod run;
 mod stop;
+mod symbolize;
+pub mod terminal;
 mod upload;
+mod watch;
 
 #[derive(clap::Parser)]
 pub struct Args {
@@ -20,15 +25,24 @@ impl super::Runnable for Args {
 	}
 }
 
+impl Broadcastable for Args {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		self.sub.broadcast(devices)
+	}
+}
+
 /// Interact with programs and execution.
 #[derive(clap::Subcommand)]
 enum Commands {
 	Info(info::Args),
 	List(list::Args),
+	Log(log::Args),
 	Remove(remove::Args),
 	Run(run::Args),
 	Stop(stop::Args),
+	Terminal(terminal::Args),
 	Upload(upload::Args),
+	Watch(watch::Args),
 }
 
 impl Runnable for Commands {
@@ -36,10 +50,23 @@ impl Runnable for Commands {
 		match self {
 			Commands::Info(args) => args.run(dev),
 			Commands::List(args) => args.run(dev),
+			Commands::Log(args) => args.run(dev),
 			Commands::Remove(args) => args.run(dev),
 			Commands::Run(args) => args.run(dev),
 			Commands::Stop(args) => args.run(dev),
+			Commands::Terminal(args) => args.run(dev),
 			Commands::Upload(args) => args.run(dev),
+			Commands::Watch(args) => args.run(dev),
+		}
+	}
+}
+
+impl Broadcastable for Commands {
+	fn broadcast(self, devices: Devices) -> anyhow::Result<()> {
+		match self {
+			Commands::Remove(args) => args.broadcast(devices),
+			Commands::Upload(args) => args.broadcast(devices),
+			_ => unsupported_broadcast(),
 		}
 	}
 }
@@ -1,10 +1,10 @@
-use crate::commands::Runnable;
+use crate::commands::{Broadcast, Runnable};
 use anyhow::Context;
 use std::collections::HashSet;
 use v5_device::program::{self, SlotNumber};
 
 /// Run a program.
-#[derive(clap::Parser)]
+#[derive(clap::Parser, Clone)]
 pub struct Args {
 	/// If true, the list is ignored and all programs are removed.
 	#[clap(long, short, group = "programs")]
@@ -34,3 +34,5 @@ impl Runnable for Args {
 		Ok(())
 	}
 }
+
+impl Broadcast for Args {}
@@ -1,3 +1,4 @@
+use super::{symbolize, terminal};
 use crate::commands::Runnable;
 use anyhow::Context;
 use std::str::FromStr;
@@ -12,10 +13,16 @@ pub struct Args {
 	raw: bool,
 	/// The slot number (or qualified filename if `--raw`) to execute.
 	slot: String,
+	/// Afterwards, show the program's output and send it standard input, like `program terminal`.
+	#[clap(long)]
+	attach: bool,
+	#[clap(flatten)]
+	symbolize: symbolize::Args,
 }
 
 impl Runnable for Args {
 	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut symbols = self.symbolize.load()?;
 		let mut dev = dev.as_result()?;
 		if self.raw {
 			let file = QualFileName::from_str(&self.slot).context("Filename")?;
@@ -24,6 +31,9 @@ impl Runnable for Args {
 			let slot = SlotNumber::from_str(&self.slot).context("Slot number")?;
 			program::run(&mut dev, slot).context("Running program")?;
 		}
+		if self.attach {
+			terminal::attach(&mut dev, false, &mut symbols)?;
+		}
 		Ok(())
 	}
 }
@@ -0,0 +1,50 @@
+use anyhow::Context;
+use clap_num::maybe_hex;
+use std::path::PathBuf;
+use v5_device::device::filesystem::{self as fs, Address};
+use v5_device::program::crash::{Scanner, Symbolizer};
+
+/// Options for showing where the addresses in crash reports are in the source.
+#[derive(clap::Args)]
+pub struct Args {
+	/// The ELF that the running program was built from. If specified, the addresses in crash reports from the PROS kernel are looked up in its debugging information.
+	#[clap(long)]
+	elf: Option<PathBuf>,
+	/// The address that the program was uploaded to, which the ELF's lowest segment is assumed to be loaded at. Defaults to the usual address for programs.
+	#[clap(long, parse(try_from_str=maybe_hex), requires = "elf")]
+	load_address: Option<Address>,
+}
+
+/// Annotates crash reports, if an ELF was specified.
+pub struct Symbols(Option<(Scanner, Symbolizer)>);
+
+impl Args {
+	pub fn load(&self) -> anyhow::Result<Symbols> {
+		let loaded = match self.elf {
+			Some(ref elf) => Some((Scanner::new(), Symbolizer::new(elf, self.load_address.unwrap_or(fs::DEFAULT_ADDRESS)).with_context(|| format!("Loading {}", elf.display()))?)),
+			None => None,
+		};
+		Ok(Symbols(loaded))
+	}
+}
+
+impl Symbols {
+	/// Where the address on this line of output is, if the line is part of a crash report, one line per inlined function.
+	pub fn annotate(&mut self, line: &str) -> Vec<String> {
+		let (scanner, symbolizer) = match self.0 {
+			Some(ref mut loaded) => loaded,
+			None => return Vec::new(),
+		};
+		match scanner.scan(line) {
+			Some(address) => {
+				let frames = symbolizer.symbolize(address);
+				if frames.is_empty() {
+					vec![format!("    at {:#010x} (unknown)", address.address)]
+				} else {
+					frames.iter().map(|frame| format!("    at {}", frame)).collect()
+				}
+			}
+			None => Vec::new(),
+		}
+	}
+}
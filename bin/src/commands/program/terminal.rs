@@ -0,0 +1,179 @@
+use super::symbolize::{self, Symbols};
+use crate::commands::Runnable;
+use anyhow::Context;
+use colored::Colorize;
+use log::debug;
+use std::io::{ErrorKind, Read, Write};
+use std::ops::ControlFlow;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use v5_device::device::Device;
+use v5_device::program::stream::{Decoder, Frame, Lines, Packet, StreamId};
+use v5_device::transport::Transport;
+
+/// How long to wait for output before checking for input to send.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How often to ask the device whether the program is still running, while there's no output.
+const STATUS_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Show the output of the running program and send it standard input, through the brain's user port.
+///
+/// Output from PROS programs is split into its streams: standard output is shown as is, standard error in red, kernel debugging messages in yellow, and any other stream prefixed with its name.
+/// Runs until interrupted with Ctrl-C, until the program stops, or until the user port goes away, e.g. because the brain was unplugged.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// Show the output as received instead of decoding PROS's streams, e.g. for programs that don't use PROS.
+	#[clap(long, conflicts_with = "elf")]
+	raw: bool,
+	#[clap(flatten)]
+	symbolize: symbolize::Args,
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut symbols = self.symbolize.load()?;
+		attach(&mut dev.as_result()?, self.raw, &mut symbols)
+	}
+}
+
+/// Show the program's output until it stops or the user port goes away, forwarding standard input in the meantime.
+pub fn attach(dev: &mut Device, raw: bool, symbols: &mut Symbols) -> anyhow::Result<()> {
+	let mut decoder = Decoder::new();
+	let mut lines = Lines::new();
+	read_user_port(dev, true, |data| {
+		if raw {
+			return write_flush(&mut std::io::stdout(), data);
+		}
+		for frame in decoder.push(data) {
+			show(&frame)?;
+			for (_, line) in lines.push(&frame) {
+				for annotation in symbols.annotate(&line) {
+					write_flush(&mut std::io::stderr(), format!("{}\n", annotation.magenta()).as_bytes())?;
+				}
+			}
+		}
+		Ok(())
+	})
+}
+
+/// Pass everything received on the user port to `handle` until the program stops or the port goes away, optionally forwarding standard input in the meantime.
+pub fn read_user_port(dev: &mut Device, forward_stdin: bool, mut handle: impl FnMut(&[u8]) -> anyhow::Result<()>) -> anyhow::Result<()> {
+	let port = dev.open_user_port().context("Opening the user port")?;
+	read_port(port, dev, forward_stdin, |data| {
+		if !data.is_empty() {
+			handle(data)?;
+		}
+		Ok(ControlFlow::Continue(()))
+	})
+}
+
+/// Like `read_user_port`, but for a port that was already opened, and `handle` can stop reading early.
+/// `handle` is also called with nothing whenever nothing was received for a moment, so that it can give up waiting.
+/// At those times, `dev` is also asked every so often whether the program is still running, and reading stops once it isn't.
+pub fn read_port(mut port: Box<dyn Transport>, dev: &mut Device, forward_stdin: bool, mut handle: impl FnMut(&[u8]) -> anyhow::Result<ControlFlow<()>>) -> anyhow::Result<()> {
+	port.set_timeout(POLL_INTERVAL).context("Setting the user port timeout")?;
+	let input = forward_stdin.then(read_stdin_in_background);
+	let mut buffer = [0u8; 1024];
+	let mut program = ProgramWatch::new(dev);
+	loop {
+		let flow = match port.read(&mut buffer) {
+			Ok(0) => return Ok(()),
+			Ok(amount) => handle(&buffer[..amount])?,
+			Err(err) if err.kind() == ErrorKind::TimedOut => {
+				if program.has_stopped(dev) {
+					return Ok(());
+				}
+				handle(&[])?
+			}
+			Err(err) => return Err(err).context("The user port was closed"),
+		};
+		if flow.is_break() {
+			return Ok(());
+		}
+		while let Some(data) = input.as_ref().and_then(|input| input.try_recv().ok()) {
+			port.write_all(&data).context("Sending input to the program")?;
+			port.flush().context("Sending input to the program")?;
+		}
+	}
+}
+
+/// Notices when the program stops, by asking the device whether it's running.
+struct ProgramWatch {
+	was_running: bool,
+	last_check: Instant,
+	/// Cleared if the device couldn't be asked, so that it isn't asked again and again.
+	enabled: bool,
+}
+
+impl ProgramWatch {
+	fn new(dev: &mut Device) -> Self {
+		let mut ret = Self {
+			was_running: false,
+			last_check: Instant::now(),
+			enabled: true,
+		};
+		ret.was_running = ret.check(dev).unwrap_or(false);
+		ret
+	}
+	fn check(&mut self, dev: &mut Device) -> Option<bool> {
+		self.last_check = Instant::now();
+		match dev.program_running() {
+			Ok(running) => Some(running),
+			Err(err) => {
+				debug!("Could not check whether the program is running, so not checking again: {}", err);
+				self.enabled = false;
+				None
+			}
+		}
+	}
+	/// Whether the program was running before but isn't any more. A program that wasn't running to begin with hasn't stopped.
+	fn has_stopped(&mut self, dev: &mut Device) -> bool {
+		if !self.enabled || self.last_check.elapsed() < STATUS_INTERVAL {
+			return false;
+		}
+		match self.check(dev) {
+			Some(running) => {
+				let stopped = self.was_running && !running;
+				self.was_running = running;
+				stopped
+			}
+			None => false,
+		}
+	}
+}
+
+/// Standard input is read on its own thread because there is no portable way to poll it.
+pub fn read_stdin_in_background() -> mpsc::Receiver<Vec<u8>> {
+	let (sender, receiver) = mpsc::channel();
+	std::thread::spawn(move || {
+		let mut stdin = std::io::stdin();
+		let mut buffer = [0u8; 256];
+		while let Ok(amount @ 1..) = stdin.read(&mut buffer) {
+			if sender.send(buffer[..amount].to_vec()).is_err() {
+				break;
+			}
+		}
+	});
+	receiver
+}
+
+/// Show a frame on the terminal, styled according to its stream.
+pub fn show(frame: &Frame) -> anyhow::Result<()> {
+	match frame {
+		Frame::Packet(Packet { stream: StreamId::STDOUT, data }) => write_flush(&mut std::io::stdout(), data),
+		Frame::Packet(Packet { stream: StreamId::STDERR, data }) => write_flush(&mut std::io::stderr(), String::from_utf8_lossy(data).red().to_string().as_bytes()),
+		Frame::Packet(Packet { stream: StreamId::KERNEL_DEBUG, data }) => write_flush(&mut std::io::stderr(), String::from_utf8_lossy(data).yellow().to_string().as_bytes()),
+		Frame::Packet(Packet { stream, data }) => {
+			let text = String::from_utf8_lossy(data);
+			let newline = if text.ends_with('\n') { "" } else { "\n" };
+			write_flush(&mut std::io::stdout(), format!("{} {}{}", format!("[{}]", stream).cyan(), text, newline).as_bytes())
+		}
+		Frame::Malformed(data) => write_flush(&mut std::io::stdout(), data),
+	}
+}
+
+fn write_flush(stream: &mut dyn Write, data: &[u8]) -> anyhow::Result<()> {
+	stream.write_all(data)?;
+	stream.flush()?;
+	Ok(())
+}
@@ -1,22 +1,200 @@
-use crate::commands::Runnable;
+use crate::commands::{Broadcast, Runnable};
+use crate::util::{self, broadcast::outln};
+use anyhow::Context;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use v5_device::device::filesystem::{self as fs, TransferCompleteAction};
+use v5_device::program::{self, ProgramIni, SlotNumber};
 
 /// Upload a program.
-#[derive(clap::Parser)]
+#[derive(clap::Parser, Clone)]
 pub struct Args {
-	/// Optionally override the name of the program when it's uploaded.
-	/// Defaults to the project name in Cargo.toml.
-	name: Option<String>,
+	/// The program binary to upload. With `--cold`, this is the hot binary.
+	///
+	/// This can also be an ELF, which is converted to a binary. It has to be linked for the address that the binary is placed at.
+	binary: PathBuf,
+	/// The cold package of a program that was built as a hot binary and a cold package, such as PROS's `cold.package.bin`. This can also be an ELF.
+	///
+	/// The cold package holds the libraries and runtime, and is only uploaded if it differs from the one already on the device.
+	#[clap(long)]
+	cold: Option<PathBuf>,
+	#[clap(flatten)]
+	metadata: Metadata,
 	/// Optionally specify the slot to upload to.
-	/// If not specified, uses the first non-empty slot, unless there is a program already uploaded with the same name and that is older than this version, in which case that slot is used.
+	/// If not specified, uses the first empty slot, unless there is a program already uploaded with the same name and that is not newer than this version, in which case that slot is used.
 	#[clap(short, long)]
-	slot: Option<u8>,
+	slot: Option<SlotNumber>,
 	/// When slot is specified, overwrite the slot's contents if it is already occupied.
 	#[clap(short, long, requires = "slot")]
 	force: bool,
+	/// What to do once the program is uploaded.
+	#[clap(long, arg_enum, default_value = "nothing")]
+	then: Then,
+	/// Gzip the binaries before uploading them, which makes uploads over the controller's radio faster.
+	#[clap(long, short = 'z')]
+	compress: bool,
+	/// The program binary and cold package, if they were read ahead of time.
+	#[clap(skip)]
+	data: Option<(Vec<u8>, Option<Vec<u8>>)>,
+	/// The name, version, and description, if they were resolved ahead of time.
+	#[clap(skip)]
+	resolved: Option<(String, String, String)>,
+}
+
+/// How the program is described on the brain.
+#[derive(clap::Args, Clone)]
+pub struct Metadata {
+	/// Optionally override the name of the program when it's uploaded.
+	/// Defaults to the project name in Cargo.toml.
+	#[clap(long, short)]
+	name: Option<String>,
+	/// Optionally override the version of the program.
+	/// Defaults to the project version in Cargo.toml.
+	#[clap(long)]
+	program_version: Option<String>,
+	/// Optionally override the description of the program.
+	/// Defaults to the project description in Cargo.toml.
+	#[clap(long)]
+	description: Option<String>,
+	/// The icon shown on the brain's screen.
+	#[clap(long, default_value = "USER902x.bmp")]
+	icon: String,
+}
+
+#[derive(clap::ArgEnum, Clone, Copy)]
+enum Then {
+	Nothing,
+	/// Run the program.
+	Run,
+	/// Show the program's screen, from which it can be run.
+	Screen,
+}
+
+impl From<Then> for TransferCompleteAction {
+	fn from(then: Then) -> Self {
+		match then {
+			Then::Nothing => Self::NoRun,
+			Then::Run => Self::RunImmediately,
+			Then::Screen => Self::RunScreen,
+		}
+	}
+}
+
+#[derive(Deserialize)]
+struct CargoToml {
+	/// Missing in workspace manifests.
+	package: Option<Package>,
+}
+
+#[derive(Deserialize)]
+struct Package {
+	name: String,
+	version: String,
+	#[serde(default)]
+	description: Option<String>,
+}
+
+/// The package in the closest Cargo.toml that has one, searching upwards from the current directory.
+fn find_package() -> anyhow::Result<Package> {
+	let cwd = std::env::current_dir().context("Getting current directory")?;
+	for path in cwd.ancestors().map(|dir| dir.join("Cargo.toml")).filter(|path| path.is_file()) {
+		let manifest: CargoToml = toml::from_str(&std::fs::read_to_string(&path).with_context(|| format!("Reading {}", path.display()))?).with_context(|| format!("Parsing {}", path.display()))?;
+		if let Some(package) = manifest.package {
+			return Ok(package);
+		}
+	}
+	anyhow::bail!("Could not find a Cargo.toml with a package")
+}
+
+impl Metadata {
+	/// The name, version, and description, from the arguments where specified, otherwise from Cargo.toml.
+	pub fn resolve(&self) -> anyhow::Result<(String, String, String)> {
+		if let (Some(name), Some(version)) = (&self.name, &self.program_version) {
+			return Ok((name.clone(), version.clone(), self.description.clone().unwrap_or_default()));
+		}
+		let package = find_package().context("Getting the program name and version; you can specify them with --name and --program-version instead")?;
+		Ok((
+			self.name.clone().unwrap_or(package.name),
+			self.program_version.clone().unwrap_or(package.version),
+			self.description.clone().or(package.description).unwrap_or_default(),
+		))
+	}
+	/// The description of the program in the slot, dated now, given the name, version, and description from `resolve`.
+	pub fn ini(&self, (name, version, description): (String, String, String), slot: SlotNumber) -> ProgramIni {
+		ProgramIni {
+			version,
+			name,
+			slot,
+			icon: self.icon.clone(),
+			description,
+			date: chrono::Local::now().format("%Y-%m-%dT%H:%M:%S").to_string(),
+		}
+	}
 }
 
 impl Runnable for Args {
-	fn run(self, _dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
-		todo!()
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let (data, cold) = match self.data {
+			Some(ref data) => data.clone(),
+			None => self.read_binaries()?,
+		};
+		let resolved = match self.resolved {
+			Some(ref resolved) => resolved.clone(),
+			None => self.metadata.resolve()?,
+		};
+		let (ref name, ref version, _) = resolved;
+		let programs = program::get_all(&mut dev).context("Getting program list")?;
+		let slot = match self.slot {
+			Some(slot) => {
+				if let Some(ref existing) = programs[slot.to_index()] {
+					if !self.force {
+						anyhow::bail!("Slot {} already has {}; specify --force to overwrite it", slot, existing.name);
+					}
+				}
+				slot
+			}
+			None => program::choose_slot(&programs, name, version).context("Choosing a slot")?,
+		};
+		let ini = self.metadata.ini(resolved, slot);
+		let args = program::UploadArgs {
+			action: self.then.into(),
+			compress: self.compress,
+		};
+		match cold {
+			Some(cold) => {
+				let cold_uploaded = program::upload_hot_cold(&mut dev, &ini, &data, &cold, &args).context("Uploading program")?;
+				if !cold_uploaded {
+					outln!("The cold package is already up to date");
+				}
+			}
+			None => program::upload(&mut dev, &ini, &data, &args).context("Uploading program")?,
+		}
+		outln!("Uploaded {} {} to slot {}", ini.name, ini.version, slot);
+		Ok(())
 	}
 }
+
+impl Broadcast for Args {
+	/// Read the binaries and Cargo.toml once for all devices.
+	fn prepare(&mut self) -> anyhow::Result<()> {
+		self.data = Some(self.read_binaries()?);
+		self.resolved = Some(self.metadata.resolve()?);
+		Ok(())
+	}
+}
+
+impl Args {
+	fn read_binaries(&self) -> anyhow::Result<(Vec<u8>, Option<Vec<u8>>)> {
+		let hot = util::elf::flatten(read_binary(&self.binary)?, fs::DEFAULT_ADDRESS)?;
+		let cold = match self.cold {
+			Some(ref cold) => Some(util::elf::flatten(read_binary(cold)?, program::upload::COLD_ADDRESS)?),
+			None => None,
+		};
+		Ok((hot, cold))
+	}
+}
+
+pub fn read_binary(path: &Path) -> anyhow::Result<Vec<u8>> {
+	std::fs::read(path).with_context(|| format!("Reading {}", path.display()))
+}
@@ -0,0 +1,132 @@
+use super::upload::{self, Metadata};
+use crate::commands::Runnable;
+use crate::util::{self, shell};
+use anyhow::Context;
+use log::warn;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use v5_device::device::filesystem as fs;
+use v5_device::device::Device;
+use v5_device::program::{self, SlotNumber, UploadArgs};
+
+/// Rebuild and re-upload a program whenever its source changes.
+///
+/// The build command is run at the start and after every change, and the binary is uploaded to the slot, replacing whatever is there.
+/// The upload is skipped if the slot already has the same binary. Runs until interrupted with Ctrl-C.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// The binary that the build command produces. This can also be an ELF.
+	binary: PathBuf,
+	/// The slot to upload to.
+	#[clap(short, long)]
+	slot: SlotNumber,
+	/// The shell command that builds the binary. It's run in the watched directory.
+	#[clap(long, default_value = "cargo build --release")]
+	build: String,
+	/// The directory to watch for changes.
+	#[clap(long, default_value = ".")]
+	dir: PathBuf,
+	/// The names of directories not to watch, wherever they are. Can be specified multiple times.
+	/// Whatever the build writes should be in one of them, or it counts as a change.
+	#[clap(long = "ignore", default_values = &["target", ".git"])]
+	ignore: Vec<String>,
+	/// Restart the program after each upload.
+	#[clap(long)]
+	run: bool,
+	/// Gzip the binary before uploading it.
+	#[clap(long, short = 'z')]
+	compress: bool,
+	/// How often to check for changes, in milliseconds.
+	#[clap(long, default_value = "500")]
+	interval: u64,
+	#[clap(flatten)]
+	metadata: Metadata,
+}
+
+/// When each file was last modified, and its size.
+type Snapshot = BTreeMap<PathBuf, (SystemTime, u64)>;
+
+/// Files that can't be read are left out, since they may be in the middle of being replaced.
+fn snapshot(dir: &Path, ignore: &[String], into: &mut Snapshot) {
+	let entries = match std::fs::read_dir(dir) {
+		Ok(entries) => entries,
+		Err(_) => return,
+	};
+	for entry in entries.flatten() {
+		let metadata = match entry.metadata() {
+			Ok(metadata) => metadata,
+			Err(_) => continue,
+		};
+		if metadata.is_dir() {
+			if !ignore.iter().any(|name| entry.file_name() == name.as_str()) {
+				snapshot(&entry.path(), ignore, into);
+			}
+		} else if let Ok(modified) = metadata.modified() {
+			into.insert(entry.path(), (modified, metadata.len()));
+		}
+	}
+}
+
+impl Args {
+	fn snapshot(&self) -> Snapshot {
+		let mut ret = Snapshot::new();
+		snapshot(&self.dir, &self.ignore, &mut ret);
+		ret
+	}
+	/// Wait until nothing has changed for an interval, so that we don't build in the middle of an editor saving several files.
+	/// Returns the snapshot that the build will see.
+	fn settle(&self, mut current: Snapshot) -> Snapshot {
+		loop {
+			std::thread::sleep(Duration::from_millis(self.interval));
+			let next = self.snapshot();
+			if next == current {
+				return current;
+			}
+			current = next;
+		}
+	}
+	fn build_and_upload(&self, dev: &mut Device) -> anyhow::Result<()> {
+		println!("Running {}", self.build);
+		let status = shell::command(&self.build).current_dir(&self.dir).status().context("Running the build command")?;
+		if !status.success() {
+			anyhow::bail!("The build failed: {}", status);
+		}
+		let binary = util::elf::flatten(upload::read_binary(&self.binary)?, fs::DEFAULT_ADDRESS)?;
+		let args = UploadArgs { compress: self.compress, ..Default::default() };
+		if program::upload::is_uploaded(dev, self.slot, &binary, &args).context("Checking the uploaded program")? {
+			println!("The binary is unchanged; not uploading");
+			return Ok(());
+		}
+		let ini = self.metadata.ini(self.metadata.resolve()?, self.slot);
+		program::upload(dev, &ini, &binary, &args).context("Uploading program")?;
+		println!("Uploaded {} {} to slot {}", ini.name, ini.version, self.slot);
+		if self.run {
+			dev.stop_execution().context("Stopping the program")?;
+			program::run(dev, self.slot).context("Running the program")?;
+		}
+		Ok(())
+	}
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		let mut last = None;
+		loop {
+			let current = self.snapshot();
+			if last.as_ref() != Some(&current) {
+				if last.is_some() {
+					println!("Change detected");
+				}
+				// taken before building, so that changes made during the build aren't missed
+				last = Some(self.settle(current));
+				// a failed build or upload is reported, but the next change might fix it
+				if let Err(err) = self.build_and_upload(&mut dev) {
+					warn!("{:#}", err);
+				}
+			}
+			std::thread::sleep(Duration::from_millis(self.interval));
+		}
+	}
+}
@@ -0,0 +1,53 @@
+use crate::commands::Runnable;
+use anyhow::Context;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::Duration;
+use v5_device::device::dissect::{self, Dissector};
+use v5_device::transport::capture::{self, Direction};
+
+/// Decode captured traffic into commands and responses.
+///
+/// Simple commands don't give their length, so anything sent after one up to the next command is shown as its payload.
+#[derive(clap::Parser)]
+pub struct Args {
+	/// A capture file, as recorded with `--capture`.
+	#[clap(required_unless_present_any = &["tx", "rx"], conflicts_with_all = &["tx", "rx"])]
+	capture: Option<PathBuf>,
+	/// A file with raw data sent by the host, e.g., from a USB sniffer.
+	///
+	/// Raw data has no timing information, so all sent data is decoded before all received data.
+	#[clap(long)]
+	tx: Option<PathBuf>,
+	/// A file with raw data received by the host.
+	#[clap(long)]
+	rx: Option<PathBuf>,
+}
+
+impl Runnable for Args {
+	fn run(self, _dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let frames = match self.capture {
+			Some(ref path) => {
+				let capture = capture::read_capture(&mut BufReader::new(File::open(path).context("Opening capture")?)).context("Reading capture")?;
+				dissect::dissect(&capture.events)
+			}
+			None => {
+				let mut dissector = Dissector::new();
+				let mut frames = Vec::new();
+				for (direction, path) in [(Direction::Tx, &self.tx), (Direction::Rx, &self.rx)] {
+					if let Some(path) = path {
+						let data = std::fs::read(path).with_context(|| format!("Reading raw {} data", direction))?;
+						frames.extend(dissector.feed(Duration::ZERO, direction, &data));
+					}
+				}
+				frames.extend(dissector.finish());
+				frames
+			}
+		};
+		for frame in frames {
+			println!("{}", frame);
+		}
+		Ok(())
+	}
+}
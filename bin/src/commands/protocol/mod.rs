@@ -0,0 +1,30 @@
+use crate::commands::Runnable;
+use v5_device::util::presence::Presence;
+
+mod decode;
+
+#[derive(clap::Parser)]
+pub struct Args {
+	#[clap(subcommand)]
+	sub: Commands,
+}
+
+impl Runnable for Args {
+	fn run(self, dev: Presence) -> anyhow::Result<()> {
+		self.sub.run(dev)
+	}
+}
+
+/// Work with the wire protocol directly.
+#[derive(clap::Subcommand)]
+enum Commands {
+	Decode(decode::Args),
+}
+
+impl Runnable for Commands {
+	fn run(self, dev: Presence) -> anyhow::Result<()> {
+		match self {
+			Commands::Decode(args) => args.run(dev),
+		}
+	}
+}
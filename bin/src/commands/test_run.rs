@@ -0,0 +1,166 @@
+use crate::commands::program::terminal;
+use crate::commands::Runnable;
+use crate::util;
+use anyhow::Context;
+use log::warn;
+use std::ops::ControlFlow;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+use v5_device::device::filesystem::{self as fs, QualFile};
+use v5_device::program::crash;
+use v5_device::program::stream::{Decoder, Lines};
+use v5_device::program::test_protocol::{self, TestEvent, TestOutcome};
+
+/// Where the test binary is uploaded to. It's deleted afterwards.
+const SCRATCH_FILE: &str = "reveng:test_run.bin";
+
+/// Run a test binary on the brain and report whether the tests passed, for use as a cargo runner.
+///
+/// To run `cargo test` on the brain, add this to `.cargo/config.toml`:
+///
+/// [target.armv7a-vex-v5]
+/// runner = "reveng test-run"
+///
+/// The binary is uploaded, run, and deleted afterwards. Its output is shown as it arrives, and the results are read from lines such as `test name ... ok` and `test result: ok.`, as printed by Rust's test harness.
+/// The command fails if any test failed, the program crashed, or there was no result in time.
+#[derive(clap::Parser)]
+#[clap(setting = clap::AppSettings::TrailingVarArg)]
+pub struct Args {
+	/// The test binary. This can also be an ELF.
+	binary: PathBuf,
+	/// How long to wait for the tests to finish, in seconds.
+	#[clap(long, default_value = "60")]
+	timeout: u64,
+	/// Arguments for the test binary, which can't be passed on to the brain and are ignored.
+	#[clap(allow_hyphen_values = true)]
+	test_args: Vec<String>,
+}
+
+/// What was seen of the test run so far.
+#[derive(Default)]
+struct Progress {
+	passed: usize,
+	failed: Vec<String>,
+	ignored: usize,
+	result: Option<bool>,
+	crash: crash::Scanner,
+	/// Whether a whole crash report was seen.
+	crashed: bool,
+}
+
+impl Progress {
+	fn update(&mut self, line: &str) {
+		let was_in_report = self.crash.in_report();
+		self.crash.scan(line);
+		if was_in_report && !self.crash.in_report() {
+			self.crashed = true;
+		}
+		match test_protocol::parse_line(line) {
+			Some(TestEvent::Finished { outcome: TestOutcome::Passed, .. }) => self.passed += 1,
+			Some(TestEvent::Finished { name, outcome: TestOutcome::Failed }) => self.failed.push(name),
+			Some(TestEvent::Finished { outcome: TestOutcome::Ignored, .. }) => self.ignored += 1,
+			Some(TestEvent::Summary { passed }) => self.result = Some(passed),
+			Some(TestEvent::Started { .. }) | None => {}
+		}
+	}
+	/// Whether there's no point waiting for more output, because the tests finished or the program crashed.
+	fn is_over(&self) -> bool {
+		self.result.is_some() || self.crashed
+	}
+}
+
+impl Runnable for Args {
+	fn run(self, dev: v5_device::util::presence::Presence) -> anyhow::Result<()> {
+		let mut dev = dev.as_result()?;
+		if !self.test_args.is_empty() {
+			warn!("Ignoring test arguments {:?}, which can't be passed to the brain", self.test_args);
+		}
+		let data = util::elf::flatten(std::fs::read(&self.binary).with_context(|| format!("Reading {}", self.binary.display()))?, fs::DEFAULT_ADDRESS)?;
+		// open it before running the program so that none of the output is missed
+		let port = dev.open_user_port().context("Opening the user port")?;
+		let file = QualFile::from_str(SCRATCH_FILE).unwrap();
+		let args = fs::WriteArgs {
+			address: Some(fs::DEFAULT_ADDRESS),
+			overwrite: true,
+			timestamp: fs::TimeStamp::now(),
+			..Default::default()
+		};
+		dev.write_file_from_slice(&data, &file, &args).context("Uploading the test binary")?;
+		dev.execute_file(&file.common).context("Running the test binary")?;
+
+		let deadline = Instant::now() + Duration::from_secs(self.timeout);
+		let mut progress = Progress::default();
+		let mut decoder = Decoder::new();
+		let mut lines = Lines::new();
+		let outcome = terminal::read_port(port, &mut dev, false, |data| {
+			for frame in decoder.push(data) {
+				terminal::show(&frame)?;
+				for (_, line) in lines.push(&frame) {
+					progress.update(&line);
+					if progress.is_over() {
+						return Ok(ControlFlow::Break(()));
+					}
+				}
+			}
+			if Instant::now() > deadline {
+				anyhow::bail!("The tests did not finish within {} seconds", self.timeout);
+			}
+			Ok(ControlFlow::Continue(()))
+		});
+
+		// clean up whatever happened
+		if let Err(err) = dev.stop_execution() {
+			warn!("Could not stop the test binary: {}", err);
+		}
+		if let Err(err) = dev.delete_file(&file.common, &Default::default()) {
+			warn!("Could not delete the test binary: {}", err);
+		}
+
+		outcome?;
+		eprintln!("{} passed, {} failed, {} ignored on the brain", progress.passed, progress.failed.len(), progress.ignored);
+		for name in progress.failed.iter() {
+			eprintln!("    {}", name);
+		}
+		if progress.crashed {
+			anyhow::bail!("The test binary crashed");
+		}
+		match progress.result {
+			Some(true) => Ok(()),
+			Some(false) => anyhow::bail!("Some tests failed"),
+			None => anyhow::bail!("The test binary stopped, or the user port closed, before the tests finished"),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Progress;
+
+	fn feed(lines: &[&str]) -> Progress {
+		let mut progress = Progress::default();
+		for line in lines {
+			assert!(!progress.is_over(), "Over before {:?}", line);
+			progress.update(line);
+		}
+		progress
+	}
+
+	#[test]
+	fn crash_mid_run() {
+		let progress = feed(&["running 2 tests", "test first ... ok", "DATA ABORT EXCEPTION", "PC: 0x03800104", "LR: 0x03800200", "BEGIN STACK TRACE", "0x03800200", "END OF TRACE"]);
+		assert!(progress.is_over());
+		assert!(progress.crashed);
+		assert_eq!(progress.passed, 1);
+		assert_eq!(progress.result, None);
+	}
+
+	#[test]
+	fn exception_in_output() {
+		let progress = feed(&["running 2 tests", "expecting a NUMERIC EXCEPTION", "test first ... ok", "test second ... FAILED", "test result: FAILED. 1 passed; 1 failed"]);
+		assert!(progress.is_over());
+		assert!(!progress.crashed);
+		assert_eq!(progress.failed, ["second"]);
+		assert_eq!(progress.result, Some(false));
+	}
+}
@@ -27,10 +27,11 @@ impl Log for SimpleLogger {
 		}
 		let time = unsafe { BASE_TIMESTAMP }.expect("Logging has not been properly initialized").elapsed();
 		eprintln!(
-			"[{time:0>7.3}][{level: >5}][{path: <16}]  {content}",
+			"[{time:0>7.3}][{level: >5}][{path: <16}]  {prefix}{content}",
 			time = time.as_secs_f64(),
 			level = ColorizedLevel(record.level()),
 			path = record.module_path().unwrap_or(""),
+			prefix = crate::util::broadcast::prefix(),
 			content = record.args()
 		);
 	}
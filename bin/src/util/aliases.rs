@@ -0,0 +1,28 @@
+//! The alias file, which gives names to devices by system ID.
+
+use anyhow::Context;
+use std::path::PathBuf;
+use v5_device::device::discover::selector::{parse_aliases, Aliases};
+
+/// `REVENG_ALIASES` if set, otherwise `reveng/aliases.ini` in the user's configuration directory.
+pub fn path() -> Option<PathBuf> {
+	if let Some(path) = std::env::var_os("REVENG_ALIASES") {
+		return Some(path.into());
+	}
+	let config_dir = if cfg!(target_family = "windows") {
+		std::env::var_os("APPDATA").map(PathBuf::from)
+	} else {
+		std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+	};
+	config_dir.map(|dir| dir.join("reveng").join("aliases.ini"))
+}
+
+/// A missing alias file is the same as an empty one.
+pub fn load() -> anyhow::Result<Aliases> {
+	let path = match path() {
+		Some(path) if path.exists() => path,
+		_ => return Ok(Aliases::new()),
+	};
+	let contents = std::fs::read_to_string(&path).with_context(|| format!("Reading alias file {}", path.display()))?;
+	parse_aliases(&contents).with_context(|| format!("Parsing alias file {}", path.display()))
+}
@@ -0,0 +1,70 @@
+//! Running a command on several devices at once, for `--all-devices`.
+
+use std::cell::RefCell;
+use v5_device::device::Device;
+
+thread_local! {
+	/// The device that the current thread is working on, when broadcasting.
+	static LABEL: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// What to prefix output with so that it can be told apart from the output for other devices. Empty unless broadcasting.
+pub fn prefix() -> String {
+	LABEL.with(|label| label.borrow().as_ref().map(|label| format!("[{}] ", label)).unwrap_or_default())
+}
+
+/// `println!`, but prefixed with the device when broadcasting.
+macro_rules! outln {
+	($($arg:tt)*) => {
+		println!("{}{}", $crate::util::broadcast::prefix(), format_args!($($arg)*))
+	};
+}
+pub(crate) use outln;
+
+/// Every device that was found, including those that couldn't be opened, so that they aren't skipped without notice.
+pub struct Devices {
+	pub opened: Vec<Device>,
+	/// The devices that couldn't be opened, e.g. because they were busy, each with the name of its port.
+	pub unopened: Vec<(String, anyhow::Error)>,
+}
+
+impl Devices {
+	pub fn is_empty(&self) -> bool {
+		self.opened.is_empty() && self.unopened.is_empty()
+	}
+}
+
+/// Run the job on every device in parallel, each on its own thread.
+/// Afterwards, report how each device fared, and fail if any device did. Devices that couldn't be opened count as having failed.
+pub fn run_on_all(devices: Devices, job: impl Fn(Device) -> anyhow::Result<()> + Sync) -> anyhow::Result<()> {
+	let job = &job;
+	let mut results: Vec<(String, anyhow::Result<()>)> = std::thread::scope(|scope| {
+		let threads: Vec<_> = devices
+			.opened
+			.into_iter()
+			.enumerate()
+			.map(|(idx, device)| {
+				let label = device.name().unwrap_or_else(|| format!("device {}", idx));
+				let thread_label = label.clone();
+				let thread = scope.spawn(move || {
+					LABEL.with(|label| *label.borrow_mut() = Some(thread_label));
+					job(device)
+				});
+				(label, thread)
+			})
+			.collect();
+		threads.into_iter().map(|(label, thread)| (label, thread.join().unwrap_or_else(|_| Err(anyhow::anyhow!("Panicked"))))).collect()
+	});
+	results.extend(devices.unopened.into_iter().map(|(port, err)| (port, Err(err))));
+	let num_failed = results.iter().filter(|(_, result)| result.is_err()).count();
+	for (label, result) in results.iter() {
+		match result {
+			Ok(()) => eprintln!("[{}] Succeeded", label),
+			Err(err) => eprintln!("[{}] Failed: {:#}", label, err),
+		}
+	}
+	if num_failed > 0 {
+		anyhow::bail!("{} of {} devices failed", num_failed, results.len());
+	}
+	Ok(())
+}
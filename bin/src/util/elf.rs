@@ -0,0 +1,18 @@
+use crate::util::broadcast::outln;
+use anyhow::Context;
+use v5_device::device::filesystem::Address;
+use v5_device::program::elf::{self, ElfImage};
+
+/// If the data is an ELF, the flat image to upload in its place, once it's been checked that the ELF was linked for `address`. Anything else is returned as is.
+pub fn flatten(data: Vec<u8>, address: Address) -> anyhow::Result<Vec<u8>> {
+	if !elf::is_elf(&data) {
+		return Ok(data);
+	}
+	let image = ElfImage::parse(&data).context("Reading ELF")?;
+	image.check_address(address).context("The ELF can't be uploaded there")?;
+	for section in image.sections.iter() {
+		outln!("{:<20} {:#010x} {:>8} bytes", section.name, section.address, section.size);
+	}
+	outln!("Image is {} bytes at {:#010x}", image.data.len(), image.address);
+	Ok(image.data)
+}
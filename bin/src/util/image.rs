@@ -0,0 +1,275 @@
+use anyhow::Context;
+use std::fmt;
+use std::io::{Read, Write};
+use std::str::FromStr;
+use v5_device::device::screen::{Framebuffer, Rect};
+
+/// An 8-bit RGB image held in memory, row by row.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Image {
+	pub width: usize,
+	pub height: usize,
+	pub data: Vec<u8>,
+}
+
+impl Image {
+	/// The pixels of a screen capture within `rect`.
+	pub fn from_framebuffer(framebuffer: &Framebuffer, rect: Rect) -> Self {
+		Self {
+			width: rect.width,
+			height: rect.height,
+			data: framebuffer.rgb_region(rect),
+		}
+	}
+
+	/// Read any 8-bit or 16-bit PNG, discarding alpha.
+	pub fn read_png(input: impl Read) -> anyhow::Result<Self> {
+		let mut decoder = png::Decoder::new(input);
+		decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+		let mut reader = decoder.read_info().context("Reading PNG header")?;
+		let mut buf = vec![0; reader.output_buffer_size()];
+		let info = reader.next_frame(&mut buf).context("Reading PNG data")?;
+		let buf = &buf[..info.buffer_size()];
+		let data = match info.color_type {
+			png::ColorType::Rgb => buf.to_vec(),
+			png::ColorType::Rgba => buf.chunks_exact(4).flat_map(|pixel| [pixel[0], pixel[1], pixel[2]]).collect(),
+			png::ColorType::Grayscale => buf.iter().flat_map(|&value| [value; 3]).collect(),
+			png::ColorType::GrayscaleAlpha => buf.chunks_exact(2).flat_map(|pixel| [pixel[0]; 3]).collect(),
+			png::ColorType::Indexed => unreachable!("EXPAND converts indexed images to RGB"),
+		};
+		Ok(Self {
+			width: info.width as usize,
+			height: info.height as usize,
+			data,
+		})
+	}
+
+	pub fn write_png(&self, output: impl Write) -> anyhow::Result<()> {
+		let mut encoder = png::Encoder::new(output, self.width as u32, self.height as u32);
+		encoder.set_color(png::ColorType::Rgb);
+		encoder.set_depth(png::BitDepth::Eight);
+		let mut writer = encoder.write_header().context("Writing PNG header")?;
+		writer.write_image_data(&self.data).context("Writing PNG data")?;
+		Ok(())
+	}
+
+	/// Write as a binary PPM (P6), which many tools can read and is trivial to parse.
+	pub fn write_ppm(&self, mut output: impl Write) -> anyhow::Result<()> {
+		write!(output, "P6\n{} {}\n255\n", self.width, self.height)?;
+		output.write_all(&self.data)?;
+		Ok(())
+	}
+
+	/// Write as a 24-bit uncompressed BMP.
+	pub fn write_bmp(&self, mut output: impl Write) -> anyhow::Result<()> {
+		const HEADER_SIZE: u32 = 14 + 40;
+		// rows are padded to a multiple of 4 bytes
+		let row_size = (self.width * 3).div_ceil(4) * 4;
+		let image_size = (row_size * self.height) as u32;
+		let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+		// file header
+		header.extend_from_slice(b"BM");
+		header.extend_from_slice(&(HEADER_SIZE + image_size).to_le_bytes());
+		header.extend_from_slice(&[0; 4]);
+		header.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+		// BITMAPINFOHEADER
+		header.extend_from_slice(&40u32.to_le_bytes());
+		header.extend_from_slice(&(self.width as i32).to_le_bytes());
+		// negative for rows from top to bottom
+		header.extend_from_slice(&(-(self.height as i32)).to_le_bytes());
+		header.extend_from_slice(&1u16.to_le_bytes());
+		header.extend_from_slice(&24u16.to_le_bytes());
+		// no compression
+		header.extend_from_slice(&0u32.to_le_bytes());
+		header.extend_from_slice(&image_size.to_le_bytes());
+		// resolution, and palette sizes
+		header.extend_from_slice(&[0; 16]);
+		output.write_all(&header)?;
+		let mut row = Vec::with_capacity(row_size);
+		for source in self.data.chunks_exact(self.width * 3) {
+			row.clear();
+			row.extend(source.chunks_exact(3).flat_map(|pixel| [pixel[2], pixel[1], pixel[0]]));
+			row.resize(row_size, 0);
+			output.write_all(&row)?;
+		}
+		Ok(())
+	}
+
+	pub fn pixel(&self, x: usize, y: usize) -> [u8; 3] {
+		let start = (y * self.width + x) * 3;
+		[self.data[start], self.data[start + 1], self.data[start + 2]]
+	}
+
+	/// Scale to the given size, averaging the pixels that end up in each new pixel.
+	pub fn resize(&self, width: usize, height: usize) -> Self {
+		let mut data = Vec::with_capacity(width * height * 3);
+		for y in 0..height {
+			let (y_start, y_end) = (y * self.height / height, ((y + 1) * self.height / height).max(y * self.height / height + 1));
+			for x in 0..width {
+				let (x_start, x_end) = (x * self.width / width, ((x + 1) * self.width / width).max(x * self.width / width + 1));
+				let mut sum = [0usize; 3];
+				for source_y in y_start..y_end {
+					for source_x in x_start..x_end {
+						for (total, value) in sum.iter_mut().zip(self.pixel(source_x, source_y)) {
+							*total += value as usize;
+						}
+					}
+				}
+				let count = (y_end - y_start) * (x_end - x_start);
+				data.extend(sum.map(|total| (total / count) as u8));
+			}
+		}
+		Self { width, height, data }
+	}
+
+	fn set_pixel(&mut self, x: usize, y: usize, value: [u8; 3]) {
+		let start = (y * self.width + x) * 3;
+		self.data[start..start + 3].copy_from_slice(&value);
+	}
+}
+
+/// How many pixels may differ between two images for them to count as the same, either as a number or a percentage like `0.5%`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tolerance {
+	Pixels(usize),
+	Percent(f64),
+}
+
+impl Tolerance {
+	/// Whether `differing` pixels out of `compared` are within tolerance.
+	pub fn allows(&self, differing: usize, compared: usize) -> bool {
+		match *self {
+			Self::Pixels(max) => differing <= max,
+			Self::Percent(max) => differing as f64 <= compared as f64 * max / 100.0,
+		}
+	}
+}
+
+#[derive(Debug)]
+pub struct ToleranceFromStrError;
+
+impl fmt::Display for ToleranceFromStrError {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(formatter, "expected a number of pixels or a percentage")
+	}
+}
+
+impl std::error::Error for ToleranceFromStrError {}
+
+impl FromStr for Tolerance {
+	type Err = ToleranceFromStrError;
+	fn from_str(raw: &str) -> Result<Self, Self::Err> {
+		match raw.strip_suffix('%') {
+			Some(percent) => match percent.trim().parse::<f64>() {
+				Ok(percent) if (0.0..=100.0).contains(&percent) => Ok(Self::Percent(percent)),
+				_ => Err(ToleranceFromStrError),
+			},
+			None => raw.trim().parse().map(Self::Pixels).map_err(|_| ToleranceFromStrError),
+		}
+	}
+}
+
+impl fmt::Display for Tolerance {
+	fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Pixels(pixels) => write!(formatter, "{} pixels", pixels),
+			Self::Percent(percent) => write!(formatter, "{}%", percent),
+		}
+	}
+}
+
+/// The result of comparing an image to a golden image of the same size.
+pub struct Comparison {
+	/// How many pixels were compared, i.e. were not masked out.
+	pub compared: usize,
+	pub differing: usize,
+	/// The actual image, faded, with differing pixels in red and masked regions darkened.
+	pub diff: Image,
+}
+
+/// Compare pixel by pixel, ignoring pixels in `mask`. Pixels whose channels all differ by at most `threshold` are considered equal.
+///
+/// Panics if the images are not the same size.
+pub fn compare(actual: &Image, golden: &Image, mask: &[Rect], threshold: u8) -> Comparison {
+	assert!(actual.width == golden.width && actual.height == golden.height, "Images must be the same size");
+	let mut diff = actual.clone();
+	let mut compared = 0;
+	let mut differing = 0;
+	for y in 0..actual.height {
+		for x in 0..actual.width {
+			let pixel = actual.pixel(x, y);
+			let faded = pixel.map(|channel| 192 + channel / 4);
+			if mask.iter().any(|rect| rect.contains(x, y)) {
+				diff.set_pixel(x, y, faded.map(|channel| channel / 2));
+				continue;
+			}
+			compared += 1;
+			if pixel.iter().zip(golden.pixel(x, y)).any(|(&a, b)| a.abs_diff(b) > threshold) {
+				differing += 1;
+				diff.set_pixel(x, y, [255, 0, 0]);
+			} else {
+				diff.set_pixel(x, y, faded);
+			}
+		}
+	}
+	Comparison { compared, differing, diff }
+}
+
+#[cfg(test)]
+mod tests {
+	use super::{compare, Image, Tolerance};
+	use v5_device::device::screen::Rect;
+
+	fn solid(width: usize, height: usize, value: [u8; 3]) -> Image {
+		Image {
+			width,
+			height,
+			data: value.repeat(width * height),
+		}
+	}
+
+	#[test]
+	fn parse_tolerance() {
+		assert_eq!("12".parse::<Tolerance>().unwrap(), Tolerance::Pixels(12));
+		assert_eq!("0.5%".parse::<Tolerance>().unwrap(), Tolerance::Percent(0.5));
+		assert_eq!("100%".parse::<Tolerance>().unwrap(), Tolerance::Percent(100.0));
+		assert!("-1".parse::<Tolerance>().is_err());
+		assert!("101%".parse::<Tolerance>().is_err());
+		assert!("1.5".parse::<Tolerance>().is_err());
+		assert!("%".parse::<Tolerance>().is_err());
+	}
+
+	#[test]
+	fn tolerance_allows() {
+		assert!(Tolerance::Pixels(2).allows(2, 100));
+		assert!(!Tolerance::Pixels(2).allows(3, 100));
+		assert!(Tolerance::Percent(1.0).allows(1, 100));
+		assert!(!Tolerance::Percent(1.0).allows(2, 100));
+	}
+
+	#[test]
+	fn compare_with_mask_and_threshold() {
+		let golden = solid(4, 4, [100, 100, 100]);
+		let mut actual = golden.clone();
+		// slightly off, within the threshold of 5 below
+		actual.set_pixel(0, 0, [105, 95, 100]);
+		// well off, but masked
+		actual.set_pixel(3, 3, [0, 0, 0]);
+		// well off
+		actual.set_pixel(1, 2, [200, 100, 100]);
+		let mask = [Rect { x: 2, y: 2, width: 2, height: 2 }];
+
+		let comparison = compare(&actual, &golden, &mask, 5);
+		assert_eq!(comparison.compared, 12);
+		assert_eq!(comparison.differing, 1);
+		assert_eq!(comparison.diff.pixel(1, 2), [255, 0, 0]);
+		assert_ne!(comparison.diff.pixel(0, 0), [255, 0, 0]);
+
+		let comparison = compare(&actual, &golden, &mask, 4);
+		assert_eq!(comparison.differing, 2);
+
+		let comparison = compare(&actual, &golden, &[], 5);
+		assert_eq!(comparison.compared, 16);
+		assert_eq!(comparison.differing, 2);
+	}
+}
@@ -1,2 +1,8 @@
+pub mod aliases;
+pub mod broadcast;
 pub mod diff;
+pub mod elf;
+pub mod image;
+pub mod rotating_file;
+pub mod shell;
 pub mod temp_dir;
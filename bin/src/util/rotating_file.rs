@@ -0,0 +1,57 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// A log file that is appended to a line at a time, and moved aside once it grows too large.
+/// Older files are kept next to it with a numeric suffix, e.g. `program.log.1` is the most recent, up to a limit.
+pub struct RotatingFile {
+	path: PathBuf,
+	max_size: u64,
+	num_kept: usize,
+	file: File,
+	size: u64,
+}
+
+impl RotatingFile {
+	/// Continues an existing file if there is one.
+	pub fn open(path: &Path, max_size: u64, num_kept: usize) -> std::io::Result<Self> {
+		let file = OpenOptions::new().create(true).append(true).open(path)?;
+		let size = file.metadata()?.len();
+		Ok(Self {
+			path: path.to_owned(),
+			max_size,
+			num_kept,
+			file,
+			size,
+		})
+	}
+	fn numbered_path(&self, number: usize) -> PathBuf {
+		let mut ret = self.path.clone().into_os_string();
+		ret.push(format!(".{}", number));
+		ret.into()
+	}
+	fn rotate(&mut self) -> std::io::Result<()> {
+		if self.num_kept > 0 {
+			for number in (1..self.num_kept).rev() {
+				let from = self.numbered_path(number);
+				if from.exists() {
+					fs::rename(from, self.numbered_path(number + 1))?;
+				}
+			}
+			fs::rename(&self.path, self.numbered_path(1))?;
+		}
+		self.file = File::create(&self.path)?;
+		self.size = 0;
+		Ok(())
+	}
+	/// The line is written immediately, so nothing is lost if the process is interrupted.
+	pub fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+		if self.size > 0 && self.size + line.len() as u64 + 1 > self.max_size {
+			self.rotate()?;
+		}
+		writeln!(self.file, "{}", line)?;
+		self.file.flush()?;
+		self.size += line.len() as u64 + 1;
+		Ok(())
+	}
+}
@@ -0,0 +1,16 @@
+use std::process::Command;
+
+/// A command that runs the line with the platform's shell, so that it can use pipes, `&&`, and so on.
+pub fn command(line: &str) -> Command {
+	let mut command = if cfg!(target_family = "windows") {
+		let mut command = Command::new("cmd");
+		command.arg("/C");
+		command
+	} else {
+		let mut command = Command::new("sh");
+		command.arg("-c");
+		command
+	};
+	command.arg(line);
+	command
+}
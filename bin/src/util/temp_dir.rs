@@ -31,7 +31,7 @@ impl TempDir {
 		const NUM_RAND_CHARS: usize = 24;
 		static PREFIX: &str = "tempdir-";
 		let mut rng = rand::thread_rng();
-		let random_suffix = Uniform::from('a'..'z').sample_iter(&mut rng).take(NUM_RAND_CHARS);
+		let random_suffix = Uniform::from('a'..='z').sample_iter(&mut rng).take(NUM_RAND_CHARS);
 		let mut dirname = String::with_capacity(PREFIX.len() + NUM_RAND_CHARS);
 		dirname.push_str(PREFIX);
 		dirname.extend(random_suffix);
@@ -20,6 +20,7 @@ pub trait Collectible: Default {
 	type Item;
 
 	fn update(&mut self, item: &Self::Item);
+	#[allow(dead_code)]
 	fn collect(attrs: &[Self::Item]) -> Self {
 		let mut result = Self::default();
 		for attr in attrs {
@@ -1,4 +1,5 @@
 #![cfg(test)]
+#![allow(clippy::enum_variant_names, clippy::derivable_impls)]
 
 use encde::{Decode, Encode};
 
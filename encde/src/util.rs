@@ -14,12 +14,12 @@ pub fn write_padding(writer: &mut dyn io::Write, mut amount: usize) -> io::Resul
 	Ok(())
 }
 
-static mut EMPTY_MUT_SLICE: [u8; 1024] = [0u8; 1024];
 /// Read and discard `amount` bytes from `reader`
 pub fn read_padding(reader: &mut dyn io::Read, mut amount: usize) -> io::Result<()> {
+	let mut bitbucket = [0u8; 1024];
+	let bitbucket_len = bitbucket.len();
 	while amount > 0 {
-		// Mutable statics are unsafe due to race conditions between threads, but we're using it as a bitbucket, so it doesn't matter
-		amount -= unsafe { reader.read(&mut EMPTY_MUT_SLICE[0..min(amount, EMPTY_MUT_SLICE.len())])? }
+		amount -= reader.read(&mut bitbucket[0..min(amount, bitbucket_len)])?;
 	}
 	Ok(())
 }
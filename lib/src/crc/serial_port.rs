@@ -1,28 +1,38 @@
 use super::CrcComputable;
+use crate::transport::Transport;
 use log::{debug, trace};
 use serialport::SerialPort;
 use std::io::{Read, Result, Write};
 
-/// A serial port that passively calculates the 16-bit CRC of the data that passes through it in both directions.
+/// A serial port (or any other `Transport`) that passively calculates the 16-bit CRC of the data that passes through it in both directions.
 pub struct CrcSerialPort {
-	underlying: Box<dyn SerialPort>,
+	underlying: Box<dyn Transport>,
 	tx_crc: u16,
 	rx_crc: u16,
 }
 
+impl From<Box<dyn Transport>> for CrcSerialPort {
+	fn from(underlying: Box<dyn Transport>) -> Self {
+		Self { underlying, tx_crc: 0, rx_crc: 0 }
+	}
+}
+
 impl From<Box<dyn SerialPort>> for CrcSerialPort {
 	fn from(underlying: Box<dyn SerialPort>) -> Self {
-		Self { underlying, tx_crc: 0, rx_crc: 0 }
+		Self::from(Box::new(underlying) as Box<dyn Transport>)
 	}
 }
 
 impl CrcSerialPort {
-	pub fn port(&self) -> &dyn SerialPort {
+	pub fn port(&self) -> &dyn Transport {
 		&*self.underlying
 	}
-	pub fn port_mut(&mut self) -> &mut dyn SerialPort {
+	pub fn port_mut(&mut self) -> &mut dyn Transport {
 		&mut *self.underlying
 	}
+	pub fn into_inner(self) -> Box<dyn Transport> {
+		self.underlying
+	}
 	/// Start calculating the CRC for sent data.
 	/// Use should be paired with `end_tx_crc`.
 	pub fn begin_tx_crc(&mut self) {
@@ -0,0 +1,70 @@
+//! File transfers, as in `r#impl::file_transfer`.
+
+use super::AsyncDevice;
+use crate::device::r#impl::{pad, receive as priv_receive, send as priv_send, shared, CommandId};
+use crate::device::{filesystem, DeviceError, ProtocolError, Result};
+use log::debug;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+impl AsyncDevice {
+	pub(crate) async fn start_file_transfer(&mut self, args: &priv_send::StartFileTransfer) -> Result<priv_receive::StartFileTransfer> {
+		debug!("start");
+		self.ext_command(0x11, args).await
+	}
+	/// Issue a single read command from the specified base address into the provided slice.
+	async fn ft_read_single(&mut self, data: &mut [u8], base_address: filesystem::Address) -> Result<()> {
+		const COMMAND_ID: CommandId = 0x14;
+		let amount_to_read: filesystem::PacketSize = data.len().try_into().expect("Buffer is too large to read with ft_read_single");
+		let amount_to_read = pad(amount_to_read);
+		debug!("rx chunk of {} (padded to {}) bytes", data.len(), amount_to_read);
+		let (body, crc_valid) = self.ext_command_raw(COMMAND_ID, &encde::util::encode_to_vec(&priv_send::FileTransferRead { address: base_address, size: amount_to_read })?).await?;
+		if !crc_valid {
+			return Err(DeviceError::Protocol(ProtocolError::InvalidCrc));
+		}
+		// the response has no response byte, just the address followed by the data
+		shared::check_ft_read_length(body.len(), amount_to_read)?;
+		let address_size = std::mem::size_of::<filesystem::Address>();
+		let data_len = data.len();
+		data.copy_from_slice(&body[address_size..address_size + data_len]);
+		Ok(())
+	}
+	/// Read the specified amount of data into the stream, returning its CRC.
+	pub async fn ft_read(&mut self, stream: &mut (dyn AsyncWrite + Unpin + Send), size: filesystem::FileSize, base_address: filesystem::Address, max_packet_size: filesystem::PacketSize) -> Result<u32> {
+		debug!("read {} bytes from 0x{:0>8x}, max packet size is {}", size, base_address, max_packet_size);
+		let mut buffer = vec![0u8; max_packet_size as usize];
+		let mut crc = 0;
+		for (address, this_packet_size) in shared::packets(size, base_address, max_packet_size) {
+			with_retries!(self, "file transfer read", self.ft_read_single(&mut buffer[0..this_packet_size], address).await)?;
+			<u32 as crate::crc::CrcComputable>::update_crc(&mut crc, &buffer[0..this_packet_size]);
+			stream.write_all(&buffer[0..this_packet_size]).await?;
+		}
+		Ok(crc)
+	}
+	/// Issue a single write command to the specified base address with the specified data.
+	async fn ft_write_single(&mut self, data: &[u8], base_address: filesystem::Address) -> Result<()> {
+		debug!("tx chunk of {} bytes", data.len());
+		self.ext_command_with_raw_data(0x13, &shared::ft_write_payload(data, base_address)?).await
+	}
+	/// Write the specified amount of data from the stream.
+	pub async fn ft_write(&mut self, stream: &mut (dyn AsyncRead + Unpin + Send), size: filesystem::FileSize, base_address: filesystem::Address, max_packet_size: filesystem::PacketSize) -> Result<()> {
+		debug!("write {} to 0x{:0>8x}, max packet size is {}", size, base_address, max_packet_size);
+		let mut buffer = vec![0u8; max_packet_size as usize];
+		for (address, this_packet_size) in shared::packets(size, base_address, max_packet_size) {
+			stream.read_exact(&mut buffer[0..this_packet_size]).await?;
+			with_retries!(self, "file transfer write", self.ft_write_single(&buffer[0..this_packet_size], address).await)?;
+		}
+		Ok(())
+	}
+	pub async fn ft_set_link(&mut self, linked_file: &filesystem::QualFileName) -> Result<()> {
+		debug!("set link to {}", linked_file);
+		self.ext_command(0x15, &priv_send::FileTransferSetLink::new(linked_file)).await
+	}
+	pub async fn set_transfer_channel(&mut self, channel: filesystem::Channel) -> Result<()> {
+		debug!("set channel to {}", channel);
+		self.ext_command(0x10, &priv_send::FileTransferSetChannel::new(channel)).await
+	}
+	pub async fn end_file_transfer(&mut self, action: filesystem::TransferCompleteAction) -> Result<()> {
+		debug!("end");
+		self.ext_command(0x12, &action).await
+	}
+}
@@ -0,0 +1,135 @@
+//! Sending commands and receiving their responses, as in `r#impl::commands`.
+//!
+//! Rather than computing CRCs as the data streams through the port, whole frames are assembled and checked in memory.
+
+use super::AsyncDevice;
+use crate::crc::CrcComputable;
+use crate::device::{CommandId, Device, DeviceError, ProtocolError, ResponseByte, Result};
+use encde::util::{decode_from_entire_slice, encode_to_vec};
+use encde::{Decode, Encode};
+use log::{debug, trace};
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+fn expect(entity: &'static str, expected: &[u8], received: &[u8]) -> Result<()> {
+	if expected != received {
+		Err(DeviceError::Protocol(ProtocolError::WrongData {
+			entity,
+			expected: expected.into(),
+			received: received.into(),
+		}))
+	} else {
+		Ok(())
+	}
+}
+
+impl AsyncDevice {
+	/// Mark the start of a command, first discarding the remains of the previous one if it didn't finish.
+	async fn begin_exchange(&mut self) -> Result<()> {
+		if self.in_flight {
+			debug!("previous command was interrupted, resynchronizing");
+			self.drain_input().await?;
+		}
+		self.in_flight = true;
+		Ok(())
+	}
+	fn end_exchange(&mut self) {
+		self.in_flight = false;
+	}
+
+	async fn tx_frame(&mut self, frame: &[u8]) -> Result<()> {
+		trace!("tx frame: {:?}", frame);
+		self.port.write_all(frame).await?;
+		self.port.flush().await?;
+		Ok(())
+	}
+	/// Read exactly `amount` bytes, failing with `std::io::ErrorKind::TimedOut` if they don't all arrive within the timeout.
+	async fn rx_bytes(&mut self, amount: usize) -> Result<Vec<u8>> {
+		let mut ret = vec![0u8; amount];
+		match tokio::time::timeout(self.timeout, self.port.read_exact(&mut ret)).await {
+			Ok(result) => result?,
+			Err(_elapsed) => return Err(io::Error::new(io::ErrorKind::TimedOut, "Device did not respond in time").into()),
+		};
+		trace!("rx {} bytes: {:?}", amount, ret);
+		Ok(ret)
+	}
+
+	/// Send a simple command with an arbitrary payload, returning the raw response payload.
+	pub async fn simple_command_raw(&mut self, command_id: CommandId, data: &[u8]) -> Result<Vec<u8>> {
+		debug!("simple command {:#02x}", command_id);
+		self.begin_exchange().await?;
+		let mut frame = Device::COMMAND_HEADER.to_vec();
+		frame.push(command_id);
+		frame.extend_from_slice(data);
+		self.tx_frame(&frame).await?;
+		expect("Response header", &Device::RESPONSE_HEADER, &self.rx_bytes(2).await?)?;
+		expect("Echoed command", &[command_id], &self.rx_bytes(1).await?)?;
+		let payload_length = self.rx_bytes(1).await?[0] as usize;
+		let payload = self.rx_bytes(payload_length).await?;
+		self.end_exchange();
+		Ok(payload)
+	}
+	/// Send a simple command without a payload and decode the response.
+	pub async fn simple_command<T: Decode>(&mut self, command_id: CommandId) -> Result<T> {
+		let raw = self.simple_command_raw(command_id, &[]).await?;
+		Ok(decode_from_entire_slice(&raw)?)
+	}
+
+	/// Send an extended command with an arbitrary payload.
+	/// Returns everything between the echoed command and the CRC (normally the response byte and the payload), and whether the CRC was valid.
+	pub async fn ext_command_raw(&mut self, command_id: CommandId, data: &[u8]) -> Result<(Vec<u8>, bool)> {
+		debug!("extended command {:#02x} with {} bytes of data", command_id, data.len());
+		self.begin_exchange().await?;
+		let mut frame = Device::COMMAND_HEADER.to_vec();
+		frame.extend_from_slice(&[Device::EXT_COMMAND, command_id]);
+		frame.extend(Device::encode_vex_varint(data.len())?);
+		frame.extend_from_slice(data);
+		let crc = *0u16.update_crc(&frame);
+		frame.extend_from_slice(&crc.to_be_bytes());
+		self.tx_frame(&frame).await?;
+
+		let mut received = self.rx_bytes(3).await?;
+		expect("Response header", &Device::RESPONSE_HEADER, &received[0..2])?;
+		expect("echoed command", &[Device::EXT_COMMAND], &received[2..3])?;
+		let length_start = received.len();
+		received.extend(self.rx_bytes(1).await?);
+		if Device::decode_vex_varint(&received[length_start..]).is_none() {
+			received.extend(self.rx_bytes(1).await?);
+		}
+		let (length, _) = Device::decode_vex_varint(&received[length_start..]).expect("A varint is at most two bytes");
+		// the echoed command and the CRC
+		if length < 3 {
+			return Err(DeviceError::Protocol(ProtocolError::BadLength {
+				entity: "extended response",
+				received_length: length,
+			}));
+		}
+		let rest = self.rx_bytes(length).await?;
+		received.extend_from_slice(&rest);
+		self.end_exchange();
+		expect("echoed actual command", &[command_id], &rest[0..1])?;
+		let crc_valid = *0u16.update_crc(&received) == 0;
+		Ok((rest[1..rest.len() - 2].to_vec(), crc_valid))
+	}
+	/// Send an extended command and decode the response, failing on NACKs and invalid CRCs.
+	pub async fn ext_command<S: Encode, R: Decode>(&mut self, command_id: CommandId, send_data: &S) -> Result<R> {
+		self.ext_command_with_raw_data(command_id, &encode_to_vec(send_data)?).await
+	}
+	/// `ext_command` with an already-encoded payload.
+	pub async fn ext_command_with_raw_data<R: Decode>(&mut self, command_id: CommandId, data: &[u8]) -> Result<R> {
+		let (body, crc_valid) = self.ext_command_raw(command_id, data).await?;
+		if !crc_valid {
+			return Err(DeviceError::Protocol(ProtocolError::InvalidCrc));
+		}
+		let (response_byte, payload) = body.split_first().ok_or(DeviceError::Protocol(ProtocolError::BadLength {
+			entity: "extended response payload",
+			received_length: 0,
+		}))?;
+		let response_byte: ResponseByte = decode_from_entire_slice(&[*response_byte])?;
+		if response_byte == ResponseByte::Ack {
+			Ok(decode_from_entire_slice(payload)?)
+		} else {
+			Err(ProtocolError::Nack(response_byte).into())
+		}
+	}
+}
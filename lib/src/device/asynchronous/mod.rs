@@ -0,0 +1,108 @@
+//! An async counterpart to `Device`, built on tokio, so that one task can talk to many devices at once.
+//!
+//! Building payloads and interpreting responses is shared with `Device` through `r#impl::shared`; only the framing and I/O differ.
+//! Operations can be cancelled by dropping their futures. The next command will discard whatever remains of the interrupted one before sending anything.
+//!
+//! Only available with the `async` feature.
+
+use crate::device::{Device, DeviceError, Result, RetryPolicy, UploadableInfo, UploadableType};
+use crate::transport::asynchronous::AsyncTransport;
+use log::debug;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+
+/// `Device::with_retries`, as a macro because the operation has to borrow the device across an `.await`.
+macro_rules! with_retries {
+	($device:expr, $what:expr, $operation:expr) => {{
+		let mut retries = 0;
+		loop {
+			match $operation {
+				Err(err) if $device.retry_policy.should_retry($what, &mut retries, &err) => {
+					$device.drain_input().await?;
+				}
+				ret => break ret,
+			}
+		}
+	}};
+}
+
+mod file_transfer;
+mod framing;
+mod public;
+#[cfg(test)]
+mod tests;
+
+pub struct AsyncDevice {
+	ty: UploadableType,
+	port: Box<dyn AsyncTransport>,
+	/// How long to wait for each read.
+	timeout: Duration,
+	retry_policy: RetryPolicy,
+	/// Set while a command is being sent or its response received.
+	/// If it's still set when the next command begins, the previous one was cancelled or failed partway through, so there may be stale input.
+	in_flight: bool,
+}
+
+impl Debug for AsyncDevice {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		write!(formatter, "Async device of type {} at {}", self.ty, self.port.name().as_deref().unwrap_or("(unknown)"))
+	}
+}
+
+impl AsyncDevice {
+	/// Communicate with a device of the specified type over an arbitrary transport.
+	pub fn from_transport(ty: UploadableType, transport: Box<dyn AsyncTransport>) -> Self {
+		debug!("Using async transport {} for V5 device of type {:?}", transport.name().as_deref().unwrap_or("(unknown)"), ty);
+		Self {
+			ty,
+			port: transport,
+			timeout: Device::DEFAULT_TIMEOUT,
+			retry_policy: Default::default(),
+			in_flight: false,
+		}
+	}
+	/// Open the serial port of a discovered device. This must be called within a tokio runtime.
+	pub fn open(info: UploadableInfo) -> Result<Self> {
+		debug!("Opening async serial port {} for V5 device of type {:?}", &info.name, &info.device_type);
+		let port = tokio_serial::SerialStream::open(&crate::device::r#impl::serial_port_builder(&info.name))?;
+		Ok(Self::from_transport(info.device_type, Box::new(port)))
+	}
+	pub fn into_transport(self) -> (UploadableType, Box<dyn AsyncTransport>) {
+		(self.ty, self.port)
+	}
+
+	pub fn timeout(&self) -> Duration {
+		self.timeout
+	}
+	pub fn set_timeout(&mut self, timeout: Duration) {
+		self.timeout = timeout;
+	}
+	pub fn reset_timeout(&mut self) {
+		self.set_timeout(Device::DEFAULT_TIMEOUT)
+	}
+	pub fn retry_policy(&self) -> RetryPolicy {
+		self.retry_policy
+	}
+	pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+		self.retry_policy = policy;
+	}
+
+	/// See `Device::drain_input`.
+	pub async fn drain_input(&mut self) -> Result<usize> {
+		let mut drained = 0;
+		let mut buf = [0u8; 64];
+		loop {
+			match tokio::time::timeout(Device::DRAIN_TIMEOUT, self.port.read(&mut buf)).await {
+				Err(_) | Ok(Ok(0)) => break,
+				Ok(Ok(amount)) => drained += amount,
+				Ok(Err(err)) if err.kind() == io::ErrorKind::TimedOut => break,
+				Ok(Err(err)) => return Err(DeviceError::from(err)),
+			}
+		}
+		debug!("drained {} bytes of input", drained);
+		self.in_flight = false;
+		Ok(drained)
+	}
+}
@@ -0,0 +1,142 @@
+//! The public interface, as in `r#impl::public`.
+
+use super::AsyncDevice;
+use crate::device::r#impl::{send as priv_send, shared};
+use crate::device::{filesystem, receive, send, CommandId, Device, Result};
+use log::debug;
+use tokio::io::{AsyncRead, AsyncWrite};
+
+impl AsyncDevice {
+	pub async fn device_info(&mut self) -> Result<receive::DeviceInfo> {
+		debug!("sending device info command");
+		with_retries!(self, "device info", self.simple_command(0xa4).await)
+	}
+	pub async fn extended_device_info(&mut self) -> Result<receive::ExtendedDeviceInfo> {
+		debug!("sending extended device info command");
+		const COMMAND_ID: CommandId = 0x22;
+		if shared::has_new_ext_dev_info(&self.device_info().await?) {
+			with_retries!(self, "extended device info", self.ext_command::<_, receive::ExtendedDeviceInfoNew>(COMMAND_ID, &()).await).map(receive::ExtendedDeviceInfo::from)
+		} else {
+			with_retries!(self, "extended device info", self.ext_command::<_, receive::ExtendedDeviceInfo>(COMMAND_ID, &()).await)
+		}
+	}
+
+	pub async fn system_flags(&mut self) -> Result<receive::SystemFlags> {
+		debug!("sending system flags command");
+		with_retries!(self, "system flags", self.ext_command(0x20, &()).await)
+	}
+	/// See `Device::program_running`.
+	pub async fn program_running(&mut self) -> Result<bool> {
+		Ok(self.system_flags().await?.current_program != 0)
+	}
+
+	/// `Ok(None)` is returned if the file does not exist.
+	pub async fn get_file_metadata_by_name(&mut self, args: &send::FileMetadataByName) -> Result<Option<receive::FileMetadataByName>> {
+		debug!("sending get-file-metadata-by-name command");
+		shared::not_found_as_none(with_retries!(self, "get-file-metadata-by-name", self.ext_command(0x19, args).await))
+	}
+	/// `Ok(None)` is returned if the file does not exist.
+	pub async fn get_file_metadata_by_index(&mut self, index: filesystem::FileIndex) -> Result<Option<receive::FileMetadataByIndex>> {
+		let args = send::FileMetadataByIndex::new(index);
+		shared::not_found_as_none(with_retries!(self, "get-file-metadata-by-index", self.ext_command(0x17, &args).await))
+	}
+
+	pub async fn num_files(&mut self, category: filesystem::Category) -> Result<isize> {
+		debug!("sending num-files command");
+		let args = send::NumFiles::new(category);
+		with_retries!(self, "num-files", self.ext_command(0x16, &args).await).map(|receive::NumFiles(num)| num as isize)
+	}
+	pub async fn list_all_files(&mut self, category: filesystem::Category) -> Result<Vec<receive::FileMetadataByIndex>> {
+		debug!("listing all files");
+		let num_files = shared::num_listable_files(self.num_files(category).await?);
+		let mut ret = Vec::with_capacity(num_files as usize);
+		for i in 0..num_files {
+			ret.push(self.get_file_metadata_by_index(i).await?.ok_or_else(shared::not_found)?)
+		}
+		Ok(ret)
+	}
+
+	pub async fn read_file_to_stream(&mut self, stream: &mut (dyn AsyncWrite + Unpin + Send), file: &filesystem::QualFile, args: &filesystem::ReadArgs) -> Result<()> {
+		debug!("reading file {}", file);
+		let (size, address) = match shared::read_extent(args, None) {
+			Some(extent) => extent,
+			None => {
+				let file_metadata = self.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common)).await?.ok_or_else(shared::not_found)?;
+				shared::read_extent(args, Some(&file_metadata)).expect("The metadata has both the size and the address")
+			}
+		};
+		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer::download(file, args.target, size, address)).await?;
+		let crc = self.ft_read(stream, size, address, transfer_info.max_packet_size).await?;
+		self.end_file_transfer(filesystem::TransferCompleteAction::default()).await?;
+		shared::check_read_crc(args, crc, &transfer_info)
+	}
+
+	/// See `Device::write_file_from_stream`.
+	pub async fn write_file_from_stream(&mut self, stream: &mut (dyn AsyncRead + Unpin + Send), file: &filesystem::QualFile, size: filesystem::FileSize, crc: u32, args: &filesystem::WriteArgs) -> Result<()> {
+		if args.compress {
+			let mut data = Vec::with_capacity(size as usize);
+			tokio::io::AsyncReadExt::read_to_end(&mut tokio::io::AsyncReadExt::take(&mut *stream, size as u64), &mut data).await?;
+			return self.write_file_from_slice(&data, file, args).await;
+		}
+		self.write_file_from_stream_as_is(stream, file, size, crc, args).await
+	}
+	/// `write_file_from_stream` without compression.
+	async fn write_file_from_stream_as_is(&mut self, stream: &mut (dyn AsyncRead + Unpin + Send), file: &filesystem::QualFile, size: filesystem::FileSize, crc: u32, args: &filesystem::WriteArgs) -> Result<()> {
+		let address = match args.address {
+			Some(addr) => addr,
+			None => self.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common)).await?.map(|x| x.address).unwrap_or(filesystem::DEFAULT_ADDRESS),
+		};
+		self.set_transfer_channel(filesystem::Channel::FileTransfer).await?;
+		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer::upload(file, args, size, address, crc)).await?;
+		shared::check_upload_size(&transfer_info, size)?;
+		if let Some(ref linked_file) = args.linked_file {
+			self.ft_set_link(linked_file).await?;
+		}
+		self.ft_write(stream, size, address, transfer_info.max_packet_size).await?;
+		let old_timeout = self.timeout;
+		self.set_timeout(shared::end_upload_timeout(size));
+		let ret = self.end_file_transfer(args.action).await;
+		self.set_timeout(old_timeout);
+		ret?;
+		self.set_transfer_channel(filesystem::Channel::Pit).await?;
+		Ok(())
+	}
+	/// See `Device::write_file_from_slice`.
+	pub async fn write_file_from_slice(&mut self, data: &[u8], file: &filesystem::QualFile, args: &filesystem::WriteArgs) -> Result<()> {
+		let (data, size, crc) = shared::prepare_upload(data, args);
+		let mut stream = &*data;
+		self.write_file_from_stream_as_is(&mut stream, file, size, crc, args).await
+	}
+
+	pub async fn delete_file(&mut self, file: &filesystem::QualFileName, args: &filesystem::DeleteArgs) -> Result<bool> {
+		let ret = self.ext_command::<_, ()>(0x1b, &priv_send::DeleteFile::new(file, args.include_linked)).await;
+		let was_deleted = shared::not_found_as_none(ret)?.is_some();
+		if was_deleted {
+			self.end_file_transfer(Default::default()).await?;
+		}
+		Ok(was_deleted)
+	}
+
+	/// See `Device::capture_screen`.
+	pub async fn capture_screen(&mut self, output_stream: &mut (dyn AsyncWrite + Unpin + Send)) -> Result<()> {
+		self.ext_command::<_, ()>(0x28, &()).await?;
+		self.set_transfer_channel(filesystem::Channel::FileTransfer).await?;
+		let (screen, args) = shared::screen_capture_file();
+		self.read_file_to_stream(output_stream, &screen, &args).await?;
+		self.set_transfer_channel(filesystem::Channel::Pit).await
+	}
+
+	/// See `Device::capture_screen_image`.
+	pub async fn capture_screen_image(&mut self) -> Result<crate::device::screen::Framebuffer> {
+		let mut data = Vec::with_capacity(Device::SCREEN_TOTAL_SIZE);
+		self.capture_screen(&mut data).await?;
+		Ok(crate::device::screen::Framebuffer::from_bgra(data))
+	}
+
+	pub async fn execute_file(&mut self, file: &filesystem::QualFileName) -> Result<()> {
+		self.ext_command(0x18, &priv_send::ExecuteFile::start(file)).await
+	}
+	pub async fn stop_execution(&mut self) -> Result<()> {
+		self.ext_command(0x18, &priv_send::ExecuteFile::stop()).await
+	}
+}
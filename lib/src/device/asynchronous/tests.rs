@@ -0,0 +1,58 @@
+use crate::device::filesystem::{self as fs, QualFile};
+use crate::device::{DeviceError, ProtocolError, RetryPolicy};
+use crate::emulator::VirtualBrain;
+use std::future::Future;
+use std::str::FromStr;
+
+fn block_on<F: Future>(future: F) -> F::Output {
+	tokio::runtime::Builder::new_current_thread().enable_time().build().unwrap().block_on(future)
+}
+
+#[test]
+fn write_then_read() {
+	block_on(async {
+		let brain = VirtualBrain::default();
+		let mut device = brain.connect_async();
+		assert_eq!(device.extended_device_info().await.unwrap().system_id, 0x1234_abcd);
+		let file = QualFile::from_str("user:async.bin").unwrap();
+		let data: Vec<u8> = (0..1500u32).map(|x| (x * 3) as u8).collect();
+		brain.inject_corruption(0x13);
+		device.write_file_from_slice(&data, &file, &Default::default()).await.unwrap();
+		assert_eq!(brain.file(&file.common).unwrap().data, data);
+
+		let mut output = Vec::new();
+		device.read_file_to_stream(&mut output, &file, &Default::default()).await.unwrap();
+		assert_eq!(output, data);
+		assert_eq!(device.list_all_files(fs::Category::USER).await.unwrap().len(), 1);
+	});
+}
+
+#[test]
+fn several_devices() {
+	block_on(async {
+		let brains = [VirtualBrain::default(), VirtualBrain::default()];
+		let tasks: Vec<_> = brains
+			.iter()
+			.map(|brain| {
+				let mut device = brain.connect_async();
+				tokio::spawn(async move { device.num_files(fs::Category::USER).await })
+			})
+			.collect();
+		for task in tasks {
+			assert_eq!(task.await.unwrap().unwrap(), 0);
+		}
+	});
+}
+
+#[test]
+fn recovers_after_failure() {
+	block_on(async {
+		let brain = VirtualBrain::default();
+		let mut device = brain.connect_async();
+		device.set_retry_policy(RetryPolicy::NEVER);
+		brain.inject_corruption(0x16);
+		let ret = device.num_files(fs::Category::USER).await;
+		assert!(matches!(ret, Err(DeviceError::Protocol(ProtocolError::InvalidCrc))));
+		assert_eq!(device.num_files(fs::Category::USER).await.unwrap(), 0);
+	});
+}
@@ -20,7 +20,7 @@ pub enum Classification {
 }
 
 impl Classification {
-	pub fn classify(port: &UsbPort) -> Self {
+	pub(super) fn classify(port: &UsbPort) -> Self {
 		use Classification::*;
 		match port.info.vid {
 			VEX_VENDOR_ID => match port.info.pid {
@@ -36,3 +36,16 @@ impl Classification {
 		}
 	}
 }
+
+impl std::fmt::Display for Classification {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		use Classification::*;
+		formatter.write_str(match self {
+			NotVex => "non-VEX device",
+			UnknownVexDevice => "unknown VEX device",
+			Brain => "brain",
+			BrainUser => "brain user port",
+			Controller => "controller",
+		})
+	}
+}
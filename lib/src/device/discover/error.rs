@@ -28,3 +28,32 @@ impl std::fmt::Display for UploadableInfoFromPathError {
 	}
 }
 impl std::error::Error for UploadableInfoFromPathError {}
+
+#[derive(Debug)]
+pub enum SelectError {
+	/// No connected device matched the selector.
+	NoMatch(String),
+	UnknownAlias(String),
+	InvalidAliases(String),
+	Path(UploadableInfoFromPathError),
+	SerialPortError(Error),
+}
+
+impl From<Error> for SelectError {
+	fn from(e: Error) -> SelectError {
+		SelectError::SerialPortError(e)
+	}
+}
+
+impl std::fmt::Display for SelectError {
+	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+		match self {
+			Self::NoMatch(selector) => write!(formatter, "No connected device matches {}", selector),
+			Self::UnknownAlias(alias) => write!(formatter, "Unknown device alias {}", alias),
+			Self::InvalidAliases(reason) => write!(formatter, "Invalid alias file: {}", reason),
+			Self::Path(underlying) => write!(formatter, "{}", underlying),
+			Self::SerialPortError(underlying) => write!(formatter, "Serial port error: {}", underlying),
+		}
+	}
+}
+impl std::error::Error for SelectError {}
@@ -2,8 +2,11 @@
 //! The location is the last number in the position of a USB device, and is used to distinguish between the user and system port of the VEX V5.
 //! Implementation is platform-dependent.
 
+use super::usb_port::UsbPort;
+
+/// The sysfs directory of the USB interface that a serial device belongs to, e.g. `/sys/devices/pci0000:00/0000:00:14.0/usb1/1-2/1-2:1.0`.
 #[cfg(target_os = "linux")]
-pub fn get_device_location(dev_name: &str) -> Result<u8, String> {
+fn get_interface_path(dev_name: &str) -> Result<std::path::PathBuf, String> {
 	use log::warn;
 	use std::fs;
 	use std::path::{Path, PathBuf};
@@ -20,21 +23,31 @@ pub fn get_device_location(dev_name: &str) -> Result<u8, String> {
 		// given a device /dev/ttyACM0 (dev_name = "ttyACM0"), there will be a sysfs entry under /sys/class/tty/ttyACM0
 		let sys_path: PathBuf = ["/sys/class/tty", dev_name].iter().collect();
 		// this should be a link to /sys/devices/pci????:??/????:??:*.*/usb*/*-*/*-*:*.(location)/tty/ttyACM0
-		let sys_device = fs::read_link(sys_path).map_err(|e| format!("{}", e))?;
-		// get the component with the location as a string
-		let usb_bus_info = sys_device
-			.parent()
-			.ok_or("Invalid /sys/devices path")?
-			.parent()
-			.ok_or("Invalid /sys/devices path")?
-			.file_name()
-			.ok_or("Invalid /sys/devices path")?
-			.to_str()
-			.ok_or("Non-UTF8 /sys/devices path")?;
-		// get the part after the dot (i.e., the location)
-		let location = usb_bus_info.rsplit_once('.').ok_or("Invalid USB underlying device path format")?.1;
-		location.parse().map_err(|e| format!("{}", e))
+		let sys_device = fs::read_link(&sys_path).map_err(|e| format!("{}", e))?;
+		// the link is relative to the directory containing it
+		let sys_device = sys_path.parent().unwrap().join(sys_device);
+		Ok(sys_device.parent().ok_or("Invalid /sys/devices path")?.parent().ok_or("Invalid /sys/devices path")?.to_owned())
 	} else {
 		todo!("Implement device location detection for subsystem of {}", dev_name);
 	}
 }
+
+#[cfg(target_os = "linux")]
+pub fn get_device_location(dev_name: &str) -> Result<u8, String> {
+	let interface_path = get_interface_path(dev_name)?;
+	// get the component with the location as a string
+	let usb_bus_info = interface_path.file_name().ok_or("Invalid /sys/devices path")?.to_str().ok_or("Non-UTF8 /sys/devices path")?;
+	// get the part after the dot (i.e., the location)
+	let location = usb_bus_info.rsplit_once('.').ok_or("Invalid USB underlying device path format")?.1;
+	location.parse().map_err(|e| format!("{}", e))
+}
+
+/// Whether two serial ports are interfaces of the same USB device, e.g. the system and user ports of one brain.
+/// This compares their USB parents in sysfs if possible, and otherwise their USB serial numbers.
+pub fn same_usb_device(a: &UsbPort, b: &UsbPort) -> bool {
+	#[cfg(target_os = "linux")]
+	if let (Ok(a), Ok(b)) = (get_interface_path(&a.name), get_interface_path(&b.name)) {
+		return a.parent().is_some() && a.parent() == b.parent();
+	}
+	a.info.serial_number.is_some() && a.info.serial_number == b.info.serial_number
+}
@@ -1,12 +1,17 @@
 //! Device discovery
 
-mod classification;
+pub mod classification;
 pub mod error;
 mod location;
+pub mod selector;
 pub mod uploadable_info;
 pub mod uploadable_type;
 mod usb_port;
+pub mod watch;
 
-pub use error::UploadableInfoFromPathError;
+pub use classification::Classification;
+pub use error::{SelectError, UploadableInfoFromPathError};
+pub use selector::DeviceSelector;
 pub use uploadable_info::UploadableInfo;
-pub use uploadable_type::UploadableType;
+pub use uploadable_type::{UploadableType, UploadableTypeFromStrError};
+pub use watch::{WatchEvent, Watcher};
@@ -0,0 +1,108 @@
+//! Choosing a device by something more stable than the path of its serial port, which changes whenever it's plugged in again.
+
+use super::{SelectError, UploadableInfo};
+use crate::device::helpers::SystemId;
+use crate::util::num::lenient_u64_from_str;
+use log::debug;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+/// User-defined names for devices, by system ID.
+pub type Aliases = HashMap<String, SystemId>;
+
+/// Parse an alias file, which has one `alias = system ID` pair per line, e.g. `robot-a = 0x1234abcd`.
+pub fn parse_aliases(s: &str) -> Result<Aliases, SelectError> {
+	let raw: HashMap<String, String> = serde_ini::from_str(s).map_err(|err| SelectError::InvalidAliases(err.to_string()))?;
+	raw.into_iter()
+		.map(|(alias, value)| match lenient_u64_from_str(value.trim()).ok().and_then(|id| SystemId::try_from(id).ok()) {
+			Some(system_id) => Ok((alias, system_id)),
+			None => Err(SelectError::InvalidAliases(format!("invalid system ID {:?} for alias {}", value, alias))),
+		})
+		.collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeviceSelector {
+	Path(PathBuf),
+	/// As reported in `ExtendedDeviceInfo`.
+	SystemId(SystemId),
+	UsbSerial(String),
+	/// Resolved to a system ID with `Aliases`.
+	Alias(String),
+}
+
+impl FromStr for DeviceSelector {
+	type Err = std::convert::Infallible;
+	/// In order of precedence:
+	///
+	/// - `serial:` followed by a USB serial number.
+	/// - A path, if it contains a path separator or looks like a serial port, i.e. starts with `/dev` or is `COM` followed by a number.
+	///   Whether the path exists isn't checked, so that the meaning doesn't depend on what happens to be in the current directory.
+	/// - A number, which is a system ID, e.g. `0x1234abcd`.
+	/// - Anything else is an alias.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Ok(if let Some(serial) = s.strip_prefix("serial:") {
+			Self::UsbSerial(serial.to_owned())
+		} else if s.contains(std::path::is_separator) || s.starts_with("/dev") || s.strip_prefix("COM").is_some_and(|number| !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit())) {
+			Self::Path(s.into())
+		} else if let Some(system_id) = lenient_u64_from_str(s).ok().and_then(|id| SystemId::try_from(id).ok()) {
+			Self::SystemId(system_id)
+		} else {
+			Self::Alias(s.to_owned())
+		})
+	}
+}
+
+impl Display for DeviceSelector {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Path(path) => write!(formatter, "{}", path.display()),
+			Self::SystemId(system_id) => write!(formatter, "{:#010x}", system_id),
+			Self::UsbSerial(serial) => write!(formatter, "serial:{}", serial),
+			Self::Alias(alias) => formatter.write_str(alias),
+		}
+	}
+}
+
+impl DeviceSelector {
+	/// Find the selected device among the connected ones.
+	/// Selecting by system ID (or alias) briefly opens each device to read its ID.
+	pub fn select(&self, aliases: &Aliases) -> Result<UploadableInfo, SelectError> {
+		let wanted_id = match self {
+			Self::Path(path) => return UploadableInfo::try_from(path.as_path()).map_err(SelectError::Path),
+			Self::SystemId(system_id) => *system_id,
+			Self::Alias(alias) => *aliases.get(alias).ok_or_else(|| SelectError::UnknownAlias(alias.clone()))?,
+			Self::UsbSerial(wanted) => {
+				return UploadableInfo::get_all()?.into_iter().find(|info| info.serial_number.as_ref() == Some(wanted)).ok_or_else(|| SelectError::NoMatch(self.to_string()));
+			}
+		};
+		for info in UploadableInfo::get_all()? {
+			match info.read_system_id() {
+				Ok(system_id) if system_id == wanted_id => return Ok(info),
+				Ok(_) => {}
+				Err(err) => debug!("Could not read the system ID of {}: {}", info.name, err),
+			}
+		}
+		Err(SelectError::NoMatch(self.to_string()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::DeviceSelector;
+
+	#[test]
+	fn parse() {
+		let parse = |s: &str| s.parse::<DeviceSelector>().unwrap();
+		assert_eq!(parse("serial:ABC123"), DeviceSelector::UsbSerial("ABC123".to_owned()));
+		assert_eq!(parse("/dev/does-not-exist"), DeviceSelector::Path("/dev/does-not-exist".into()));
+		assert_eq!(parse("COM3"), DeviceSelector::Path("COM3".into()));
+		assert_eq!(parse("COMPETITION"), DeviceSelector::Alias("COMPETITION".to_owned()));
+		assert_eq!(parse("0x1234abcd"), DeviceSelector::SystemId(0x1234abcd));
+		assert_eq!(parse("robot-a"), DeviceSelector::Alias("robot-a".to_owned()));
+		assert_eq!(super::parse_aliases("robot-a = 0x1234abcd\nrobot-b=42\n").unwrap()["robot-b"], 42);
+		assert!(super::parse_aliases("robot-a = nope\n").is_err());
+	}
+}
@@ -1,14 +1,25 @@
 //! This module can be used to get the list of uploadable V5 devices. (Uploadable refers to devices to which a program can be uploaded.)
 
 use super::classification::Classification;
+use super::location::same_usb_device;
 use super::usb_port::UsbPort;
 use super::{UploadableInfoFromPathError as FPError, UploadableType};
+use crate::device::helpers::SystemId;
+use crate::device::r#impl::serial_port_builder;
+use crate::device::{Device, DeviceError, RetryPolicy, SerialError};
+use crate::transport::Transport;
 use std::path::Path;
 
+#[derive(Debug, Clone)]
 pub struct UploadableInfo {
 	/// On platforms that use paths to represent serial devices (Windows, Unix, more?), this is that path.
 	pub name: String,
 	pub device_type: UploadableType,
+	/// The serial number of the USB device, if it has one. Unlike `name`, this stays the same when the device is plugged in again.
+	pub serial_number: Option<String>,
+	/// For brains, the name of the serial port connected to the user processor, which carries the program's standard input and output.
+	/// Open it with `open_user_port`.
+	pub user_port: Option<String>,
 }
 
 /// The device can possible be converted from a USB port, as long as the USB port has an uploadable device connected.
@@ -20,16 +31,31 @@ impl TryFrom<UsbPort> for UploadableInfo {
 			Classification::Brain => Ok(Self {
 				name: port.name,
 				device_type: UploadableType::Brain,
+				serial_number: port.info.serial_number,
+				user_port: None,
 			}),
 			Classification::Controller => Ok(Self {
 				name: port.name,
 				device_type: UploadableType::Controller,
+				serial_number: port.info.serial_number,
+				user_port: None,
 			}),
 			other => Err(other),
 		}
 	}
 }
 
+/// The name of the user port among `ports` that belongs to the same brain as `port`, if `port` is a brain's system port.
+pub(super) fn find_user_port(port: &UsbPort, ports: &[UsbPort]) -> Option<String> {
+	if Classification::classify(port) != Classification::Brain {
+		return None;
+	}
+	ports
+		.iter()
+		.find(|other| Classification::classify(other) == Classification::BrainUser && same_usb_device(port, other))
+		.map(|user_port| user_port.name.clone())
+}
+
 /// You can get UploadableInfo for a Path, but it's not very elegant or performant.
 impl TryFrom<&Path> for UploadableInfo {
 	type Error = FPError;
@@ -44,7 +70,26 @@ impl TryFrom<&Path> for UploadableInfo {
 }
 
 impl UploadableInfo {
+	/// Brains are paired with their user ports.
 	pub fn get_all() -> serialport::Result<Vec<UploadableInfo>> {
-		Ok(UsbPort::get_all()?.into_iter().filter_map(|x| UploadableInfo::try_from(x).ok()).collect::<Vec<_>>())
+		let ports = UsbPort::get_all()?;
+		let user_ports: Vec<Option<String>> = ports.iter().map(|port| find_user_port(port, &ports)).collect();
+		Ok(ports
+			.into_iter()
+			.zip(user_ports)
+			.filter_map(|(port, user_port)| UploadableInfo::try_from(port).ok().map(|info| UploadableInfo { user_port, ..info }))
+			.collect::<Vec<_>>())
+	}
+	/// Open the brain's user port, e.g. to read the program's output.
+	/// It has a read timeout of `Device::DEFAULT_TIMEOUT`, which can be changed with `Transport::set_timeout`.
+	pub fn open_user_port(&self) -> crate::device::Result<Box<dyn Transport>> {
+		let name = self.user_port.as_ref().ok_or(DeviceError::Serial(SerialError::NoDevice))?;
+		Ok(Box::new(serial_port_builder(name).open()?))
+	}
+	/// Briefly open the device to ask for its system ID. Commands aren't retried, so that unresponsive devices are skipped quickly.
+	pub fn read_system_id(&self) -> crate::device::Result<SystemId> {
+		let mut device = Device::try_from(self.clone())?;
+		device.set_retry_policy(RetryPolicy::NEVER);
+		Ok(device.extended_device_info()?.system_id)
 	}
 }
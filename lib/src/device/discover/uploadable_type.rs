@@ -13,3 +13,26 @@ impl std::fmt::Display for UploadableType {
 		})
 	}
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadableTypeFromStrError;
+
+impl std::fmt::Display for UploadableTypeFromStrError {
+	fn fmt(&self, fmt: &mut std::fmt::Formatter<'_>) -> Result<(), std::fmt::Error> {
+		fmt.write_str("unknown device type; expected `brain` or `controller`")
+	}
+}
+
+impl std::error::Error for UploadableTypeFromStrError {}
+
+/// The inverse of `Display`.
+impl std::str::FromStr for UploadableType {
+	type Err = UploadableTypeFromStrError;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"brain" => Ok(Self::Brain),
+			"controller" => Ok(Self::Controller),
+			_ => Err(UploadableTypeFromStrError),
+		}
+	}
+}
@@ -0,0 +1,120 @@
+//! Noticing when devices are plugged in or unplugged.
+//!
+//! This works by polling the list of serial ports, so it works anywhere that `UploadableInfo::get_all` does.
+
+use super::classification::Classification;
+use super::uploadable_info::find_user_port;
+use super::usb_port::UsbPort;
+use super::{UploadableInfo, UploadableType};
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+/// A serial port belonging to a VEX device.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WatchedPort {
+	pub name: String,
+	/// One of `Brain`, `BrainUser`, or `Controller`.
+	pub classification: Classification,
+	pub serial_number: Option<String>,
+	/// For brains, the name of their user port. See `UploadableInfo::user_port`.
+	/// If it shows up after the system port, the brain is reported as detached and attached again with it.
+	pub user_port: Option<String>,
+}
+
+impl WatchedPort {
+	/// Ports that can be communicated with as a `Device`, i.e., all but the brain's user port.
+	pub fn uploadable(&self) -> Option<UploadableInfo> {
+		let device_type = match self.classification {
+			Classification::Brain => UploadableType::Brain,
+			Classification::Controller => UploadableType::Controller,
+			_ => return None,
+		};
+		Some(UploadableInfo {
+			name: self.name.clone(),
+			device_type,
+			serial_number: self.serial_number.clone(),
+			user_port: self.user_port.clone(),
+		})
+	}
+}
+
+impl Display for WatchedPort {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		write!(formatter, "{} {}", self.classification, self.name)?;
+		if let Some(ref serial_number) = self.serial_number {
+			write!(formatter, " (serial {})", serial_number)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WatchEvent {
+	Attached(WatchedPort),
+	Detached(WatchedPort),
+}
+
+impl Display for WatchEvent {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Attached(port) => write!(formatter, "attached {}", port),
+			Self::Detached(port) => write!(formatter, "detached {}", port),
+		}
+	}
+}
+
+pub struct Watcher {
+	interval: Duration,
+	/// The ports as of the last poll.
+	known: Vec<WatchedPort>,
+}
+
+impl Watcher {
+	pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(500);
+
+	/// Devices that are already connected are reported as attached by the first poll.
+	pub fn new(interval: Duration) -> Self {
+		Self { interval, known: Vec::new() }
+	}
+
+	fn current() -> serialport::Result<Vec<WatchedPort>> {
+		let ports = UsbPort::get_all()?;
+		Ok(ports
+			.iter()
+			.filter_map(|port| match Classification::classify(port) {
+				classification @ (Classification::Brain | Classification::BrainUser | Classification::Controller) => Some(WatchedPort {
+					name: port.name.clone(),
+					classification,
+					serial_number: port.info.serial_number.clone(),
+					user_port: find_user_port(port, &ports),
+				}),
+				_ => None,
+			})
+			.collect())
+	}
+	/// Check for changes since the last poll, without waiting. Detachments are reported before attachments.
+	pub fn poll(&mut self) -> serialport::Result<Vec<WatchEvent>> {
+		let current = Self::current()?;
+		let detached = self.known.iter().filter(|port| !current.contains(port)).cloned().map(WatchEvent::Detached);
+		let attached = current.iter().filter(|port| !self.known.contains(port)).cloned().map(WatchEvent::Attached);
+		let events = detached.chain(attached).collect();
+		self.known = current;
+		Ok(events)
+	}
+	/// Poll until something changes.
+	pub fn wait(&mut self) -> serialport::Result<Vec<WatchEvent>> {
+		loop {
+			let events = self.poll()?;
+			if !events.is_empty() {
+				return Ok(events);
+			}
+			std::thread::sleep(self.interval);
+		}
+	}
+}
+
+impl Default for Watcher {
+	fn default() -> Self {
+		Self::new(Self::DEFAULT_INTERVAL)
+	}
+}
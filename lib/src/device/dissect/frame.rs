@@ -0,0 +1,125 @@
+//! Splitting byte streams into frames.
+
+use super::Frame;
+use crate::crc::CrcComputable;
+use crate::device::{CommandId, Device};
+
+/// The file transfer read response is the only one without a response byte.
+const FT_READ: CommandId = 0x14;
+
+pub enum Parsed {
+	/// A frame, and the number of bytes it took up.
+	Frame(Frame, usize),
+	/// The number of bytes at the start of the data that can't be part of a frame.
+	Garbage(usize),
+	Incomplete,
+}
+
+/// Find the header, reporting anything before it as garbage.
+fn find_header(data: &[u8], header: &[u8]) -> Option<Parsed> {
+	match data.windows(header.len()).position(|window| window == header) {
+		Some(0) => None,
+		Some(start) => Some(Parsed::Garbage(start)),
+		// keep what could be the start of a header
+		None => match data.len().saturating_sub(header.len() - 1) {
+			0 => Some(Parsed::Incomplete),
+			amount => Some(Parsed::Garbage(amount)),
+		},
+	}
+}
+
+/// Parse a command sent by the host. `at_end` is whether no more data will follow.
+///
+/// Simple commands don't give the length of their payload, so anything up to the next command header is taken to be the payload.
+/// A simple command is therefore only complete once the next command starts, or the data ends.
+pub fn parse_command(data: &[u8], at_end: bool) -> Parsed {
+	if let Some(ret) = find_header(data, &Device::COMMAND_HEADER) {
+		return ret;
+	}
+	let command = match data.get(Device::COMMAND_HEADER.len()) {
+		Some(&command) => command,
+		None => return Parsed::Incomplete,
+	};
+	if command != Device::EXT_COMMAND {
+		let payload_start = Device::COMMAND_HEADER.len() + 1;
+		let payload_len = match data[payload_start..].windows(Device::COMMAND_HEADER.len()).position(|window| window == Device::COMMAND_HEADER) {
+			Some(payload_len) => payload_len,
+			None if at_end => data.len() - payload_start,
+			None => return Parsed::Incomplete,
+		};
+		let frame = Frame::SimpleCommand {
+			command,
+			payload: data[payload_start..payload_start + payload_len].to_vec(),
+		};
+		return Parsed::Frame(frame, payload_start + payload_len);
+	}
+	let (command, (payload_len, varint_len)) = match (data.get(Device::COMMAND_HEADER.len() + 1), Device::decode_vex_varint(data.get(Device::COMMAND_HEADER.len() + 2..).unwrap_or_default())) {
+		(Some(&command), Some(length)) => (command, length),
+		_ => return Parsed::Incomplete,
+	};
+	let payload_start = Device::COMMAND_HEADER.len() + 2 + varint_len;
+	let frame_len = payload_start + payload_len + std::mem::size_of::<u16>();
+	if data.len() < frame_len {
+		return Parsed::Incomplete;
+	}
+	let frame = Frame::ExtCommand {
+		command,
+		payload: data[payload_start..payload_start + payload_len].to_vec(),
+		crc_valid: *0u16.update_crc(&data[0..frame_len]) == 0,
+	};
+	Parsed::Frame(frame, frame_len)
+}
+
+/// Parse a response sent by the device. Responses always give their length, so `at_end` makes no difference.
+pub fn parse_response(data: &[u8], _at_end: bool) -> Parsed {
+	if let Some(ret) = find_header(data, &Device::RESPONSE_HEADER) {
+		return ret;
+	}
+	match data.get(Device::RESPONSE_HEADER.len()) {
+		None => Parsed::Incomplete,
+		Some(&Device::EXT_COMMAND) => {
+			let (length, varint_len) = match Device::decode_vex_varint(&data[Device::RESPONSE_HEADER.len() + 1..]) {
+				Some(length) => length,
+				None => return Parsed::Incomplete,
+			};
+			let content_start = Device::RESPONSE_HEADER.len() + 1 + varint_len;
+			let frame_len = content_start + length;
+			if data.len() < frame_len {
+				return Parsed::Incomplete;
+			}
+			// the length includes the echoed command and the CRC
+			let content = match data.get(content_start..frame_len - std::mem::size_of::<u16>()) {
+				Some(content) if !content.is_empty() => content,
+				_ => return Parsed::Garbage(frame_len),
+			};
+			let command = content[0];
+			let (response_byte, payload) = match &content[1..] {
+				[] => (None, &[][..]),
+				payload if command == FT_READ && payload.len() != 1 => (None, payload),
+				[response_byte, payload @ ..] => (Some(*response_byte), payload),
+			};
+			let frame = Frame::ExtResponse {
+				command,
+				response_byte,
+				payload: payload.to_vec(),
+				crc_valid: *0u16.update_crc(&data[0..frame_len]) == 0,
+			};
+			Parsed::Frame(frame, frame_len)
+		}
+		Some(&command) => {
+			let payload_start = Device::RESPONSE_HEADER.len() + 2;
+			let payload_len = match data.get(payload_start - 1) {
+				Some(&length) => length as usize,
+				None => return Parsed::Incomplete,
+			};
+			if data.len() < payload_start + payload_len {
+				return Parsed::Incomplete;
+			}
+			let frame = Frame::SimpleResponse {
+				command,
+				payload: data[payload_start..payload_start + payload_len].to_vec(),
+			};
+			Parsed::Frame(frame, payload_start + payload_len)
+		}
+	}
+}
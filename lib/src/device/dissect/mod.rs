@@ -0,0 +1,179 @@
+//! Offline dissection of captured traffic into frames, for reverse engineering and debugging.
+//!
+//! Payloads are decoded with the same structures that `Device` uses to send and receive them.
+
+use crate::device::{CommandId, ResponseByte};
+use crate::transport::capture::{Direction, Event};
+use crate::util::hex;
+use encde::util::decode_from_entire_slice;
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+
+mod frame;
+mod payload;
+#[cfg(test)]
+mod tests;
+
+use frame::Parsed;
+pub use payload::{ext_command_name, simple_command_name};
+
+/// A single command or response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+	SimpleCommand {
+		command: CommandId,
+		/// Whatever was sent before the next command. Normally empty, but arbitrary data can be sent with `Device::raw_simple_command`.
+		payload: Vec<u8>,
+	},
+	SimpleResponse {
+		command: CommandId,
+		payload: Vec<u8>,
+	},
+	ExtCommand {
+		command: CommandId,
+		payload: Vec<u8>,
+		crc_valid: bool,
+	},
+	ExtResponse {
+		command: CommandId,
+		/// Kept raw so that unknown values can be shown. `None` for file transfer reads, which have no response byte.
+		response_byte: Option<u8>,
+		payload: Vec<u8>,
+		crc_valid: bool,
+	},
+	/// Data that is not part of any frame.
+	Unknown(Vec<u8>),
+}
+
+/// A frame along with where it came from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DissectedFrame {
+	/// The time of the data that completed the frame.
+	pub time: Duration,
+	pub direction: Direction,
+	pub frame: Frame,
+}
+
+fn crc_status(crc_valid: bool) -> &'static str {
+	if crc_valid {
+		"crc ok"
+	} else {
+		"CRC MISMATCH"
+	}
+}
+
+impl Display for DissectedFrame {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		write!(formatter, "[{:>10.6}] {} ", self.time.as_secs_f64(), self.direction)?;
+		let description = match self.frame {
+			Frame::SimpleCommand { command, ref payload } => {
+				write!(formatter, "simple {:#04x} ({})", command, simple_command_name(command).unwrap_or("unknown"))?;
+				hex::encode(payload)
+			}
+			Frame::SimpleResponse { command, ref payload } => {
+				write!(formatter, "simple {:#04x} ({})", command, simple_command_name(command).unwrap_or("unknown"))?;
+				payload::describe_simple(command, payload)
+			}
+			Frame::ExtCommand { command, ref payload, crc_valid } => {
+				write!(formatter, "ext {:#04x} ({}) [{}]", command, ext_command_name(command).unwrap_or("unknown"), crc_status(crc_valid))?;
+				payload::describe_ext(self.direction, command, payload)
+			}
+			Frame::ExtResponse {
+				command,
+				response_byte,
+				ref payload,
+				crc_valid,
+			} => {
+				write!(formatter, "ext {:#04x} ({}) [{}]", command, ext_command_name(command).unwrap_or("unknown"), crc_status(crc_valid))?;
+				match response_byte.map(|byte| (byte, decode_from_entire_slice::<ResponseByte>(&[byte]))) {
+					None | Some((_, Ok(ResponseByte::Ack))) => payload::describe_ext(self.direction, command, payload),
+					Some((_, Ok(nack))) => {
+						write!(formatter, " NACK {}", nack)?;
+						hex::encode(payload)
+					}
+					Some((byte, Err(_))) => {
+						write!(formatter, " unknown response byte {:#04x}", byte)?;
+						hex::encode(payload)
+					}
+				}
+			}
+			Frame::Unknown(ref data) => {
+				formatter.write_str("unknown data")?;
+				hex::encode(data)
+			}
+		};
+		if !description.is_empty() {
+			write!(formatter, ": {}", description)?;
+		}
+		Ok(())
+	}
+}
+
+/// Splits the data in each direction into frames as it arrives.
+#[derive(Default)]
+pub struct Dissector {
+	tx: Vec<u8>,
+	rx: Vec<u8>,
+	time: Duration,
+}
+
+impl Dissector {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Add data, returning any frames that are now complete.
+	///
+	/// A simple command is only returned once the next command starts, since its payload runs until then.
+	pub fn feed(&mut self, time: Duration, direction: Direction, data: &[u8]) -> Vec<DissectedFrame> {
+		self.time = time;
+		self.buffer(direction).extend_from_slice(data);
+		self.parse(direction, false)
+	}
+	/// Any frames that end with the data, followed by any leftover data that did not make up a complete frame.
+	pub fn finish(mut self) -> Vec<DissectedFrame> {
+		let time = self.time;
+		let mut ret = self.parse(Direction::Tx, true);
+		ret.extend(self.parse(Direction::Rx, true));
+		ret.extend(
+			[(Direction::Tx, self.tx), (Direction::Rx, self.rx)]
+				.into_iter()
+				.filter(|(_, data)| !data.is_empty())
+				.map(|(direction, data)| DissectedFrame { time, direction, frame: Frame::Unknown(data) }),
+		);
+		ret
+	}
+	fn buffer(&mut self, direction: Direction) -> &mut Vec<u8> {
+		match direction {
+			Direction::Tx => &mut self.tx,
+			Direction::Rx => &mut self.rx,
+		}
+	}
+	/// Take as many frames as possible from the start of the buffered data.
+	fn parse(&mut self, direction: Direction, at_end: bool) -> Vec<DissectedFrame> {
+		let time = self.time;
+		let parse: fn(&[u8], bool) -> Parsed = match direction {
+			Direction::Tx => frame::parse_command,
+			Direction::Rx => frame::parse_response,
+		};
+		let buffer = self.buffer(direction);
+		let mut ret = Vec::new();
+		loop {
+			let (frame, consumed) = match parse(buffer, at_end) {
+				Parsed::Frame(frame, consumed) => (frame, consumed),
+				Parsed::Garbage(consumed) => (Frame::Unknown(buffer[0..consumed].to_vec()), consumed),
+				Parsed::Incomplete => break,
+			};
+			buffer.drain(0..consumed);
+			ret.push(DissectedFrame { time, direction, frame });
+		}
+		ret
+	}
+}
+
+/// Dissect an entire capture.
+pub fn dissect(events: &[Event]) -> Vec<DissectedFrame> {
+	let mut dissector = Dissector::new();
+	let mut ret: Vec<DissectedFrame> = events.iter().flat_map(|event| dissector.feed(event.time, event.direction, &event.data)).collect();
+	ret.extend(dissector.finish());
+	ret
+}
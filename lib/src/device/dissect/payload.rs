@@ -0,0 +1,86 @@
+//! Decoding payloads with the same structures that `Device` uses.
+
+use crate::device::r#impl::{receive as priv_receive, send as priv_send};
+use crate::device::{filesystem, receive, send, CommandId};
+use crate::transport::capture::Direction;
+use crate::util::hex::encode as hex;
+use encde::util::{decode_from_entire_slice, decode_from_slice};
+use encde::Decode;
+use std::fmt::Debug;
+
+/// A human-readable name for a simple command.
+pub fn simple_command_name(command: CommandId) -> Option<&'static str> {
+	match command {
+		0xa4 => Some("device info"),
+		_ => None,
+	}
+}
+
+/// A human-readable name for an extended command.
+pub fn ext_command_name(command: CommandId) -> Option<&'static str> {
+	Some(match command {
+		0x10 => "set transfer channel",
+		0x11 => "start file transfer",
+		0x12 => "end file transfer",
+		0x13 => "file transfer write",
+		0x14 => "file transfer read",
+		0x15 => "file transfer set link",
+		0x16 => "num files",
+		0x17 => "file metadata by index",
+		0x18 => "execute file",
+		0x19 => "file metadata by name",
+		0x1b => "delete file",
+		0x20 => "system flags",
+		0x22 => "extended device info",
+		0x28 => "prepare screen capture",
+		_ => return None,
+	})
+}
+
+fn debug_decode<T: Decode + Debug>(payload: &[u8]) -> encde::Result<String> {
+	decode_from_entire_slice::<T>(payload).map(|decoded| format!("{:?}", decoded))
+}
+
+/// A file transfer packet is an address followed by the data, which is summarized rather than printed.
+fn describe_packet(payload: &[u8]) -> encde::Result<String> {
+	let (address, data_len): (filesystem::Address, usize) = decode_from_slice(payload)?;
+	Ok(format!("Packet {{ address: {:#010x}, data: {} bytes }}", address, data_len))
+}
+
+/// Decode the payload of a simple command (only responses have payloads).
+pub fn describe_simple(command: CommandId, payload: &[u8]) -> String {
+	let ret = match command {
+		0xa4 => debug_decode::<receive::DeviceInfo>(payload),
+		_ => return hex(payload),
+	};
+	ret.unwrap_or_else(|e| format!("(could not decode: {}) {}", e, hex(payload)))
+}
+
+/// Decode the payload of an extended command or of its ACKed response.
+pub fn describe_ext(direction: Direction, command: CommandId, payload: &[u8]) -> String {
+	if payload.is_empty() {
+		return String::new();
+	}
+	let ret = match (direction, command) {
+		(Direction::Tx, 0x10) => debug_decode::<priv_send::FileTransferSetChannel>(payload),
+		(Direction::Tx, 0x11) => debug_decode::<priv_send::StartFileTransfer>(payload),
+		(Direction::Rx, 0x11) => debug_decode::<priv_receive::StartFileTransfer>(payload),
+		(Direction::Tx, 0x12) => debug_decode::<filesystem::TransferCompleteAction>(payload),
+		(Direction::Tx, 0x13) | (Direction::Rx, 0x14) => describe_packet(payload),
+		(Direction::Tx, 0x14) => debug_decode::<priv_send::FileTransferRead>(payload),
+		(Direction::Tx, 0x15) => debug_decode::<priv_send::FileTransferSetLink>(payload),
+		(Direction::Tx, 0x16) => debug_decode::<send::NumFiles>(payload),
+		(Direction::Rx, 0x16) => debug_decode::<receive::NumFiles>(payload),
+		(Direction::Tx, 0x17) => debug_decode::<send::FileMetadataByIndex>(payload),
+		(Direction::Rx, 0x17) => debug_decode::<receive::FileMetadataByIndex>(payload),
+		(Direction::Tx, 0x18) => debug_decode::<priv_send::ExecuteFile>(payload),
+		(Direction::Tx, 0x19) => debug_decode::<send::FileMetadataByName>(payload),
+		(Direction::Rx, 0x19) => debug_decode::<receive::FileMetadataByName>(payload),
+		(Direction::Tx, 0x1b) => debug_decode::<priv_send::DeleteFile>(payload),
+		(Direction::Rx, 0x20) => debug_decode::<receive::SystemFlags>(payload),
+		// the format depends on the version, so try the newer one first
+		(Direction::Rx, 0x22) => debug_decode::<receive::ExtendedDeviceInfoNew>(payload).or_else(|_| debug_decode::<receive::ExtendedDeviceInfo>(payload)),
+		_ => return hex(payload),
+	};
+	ret.unwrap_or_else(|e| format!("(could not decode: {}) {}", e, hex(payload)))
+}
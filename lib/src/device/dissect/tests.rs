@@ -0,0 +1,72 @@
+use super::{dissect, Dissector, Frame};
+use crate::device::{filesystem as fs, Device};
+use crate::device::{ResponseByte, UploadableType};
+use crate::emulator::{VirtualBrain, VirtualFile};
+use crate::test_util::SharedBuffer;
+use crate::transport::capture::{read_capture, Direction, Recorder};
+use std::io;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[test]
+fn dissect_session() {
+	let brain = VirtualBrain::default();
+	let file = fs::QualFile::from_str("slot_1.bin").unwrap();
+	brain.insert_file(VirtualFile::new(file.common, file.ty, vec![1; 100]));
+	let capture = SharedBuffer::default();
+	let mut device = Device::from_transport(UploadableType::Brain, Box::new(Recorder::new(Box::new(brain.clone()), Box::new(capture.clone()), UploadableType::Brain))).unwrap();
+	device.device_info().unwrap();
+	device.read_file_to_stream(&mut io::sink(), &file, &Default::default()).unwrap();
+	brain.inject_nack(0x1b, ResponseByte::Enospc);
+	assert!(device.delete_file(&file.common, &Default::default()).is_err());
+	drop(device);
+
+	let events = read_capture(&mut &capture.contents()[..]).unwrap().events;
+	let frames = dissect(&events);
+	let commands: Vec<_> = frames
+		.iter()
+		.filter_map(|frame| match frame.frame {
+			Frame::SimpleCommand { command, .. } | Frame::ExtCommand { command, crc_valid: true, .. } => Some(command),
+			_ => None,
+		})
+		.collect();
+	// device info, then metadata by name, start transfer, read, end transfer, then delete
+	assert_eq!(commands, [0xa4, 0x19, 0x11, 0x14, 0x12, 0x1b]);
+	assert!(frames.iter().all(|frame| !matches!(frame.frame, Frame::Unknown(_))));
+	let last = frames.last().unwrap();
+	assert_eq!(last.direction, Direction::Rx);
+	assert!(last.to_string().contains("NACK No space left on device"), "{}", last);
+}
+
+#[test]
+fn garbage_and_bad_crc() {
+	let mut dissector = Dissector::new();
+	let frames = dissector.feed(Duration::ZERO, Direction::Tx, &[0x01, 0x02, 0xc9, 0x36, 0xb8, 0x47, 0x56, 0x22, 0x00, 0x12]);
+	assert_eq!(frames[0].frame, Frame::Unknown(vec![0x01, 0x02]));
+	assert_eq!(frames.len(), 1);
+	let frames = dissector.feed(Duration::ZERO, Direction::Tx, &[0x34]);
+	assert_eq!(
+		frames[0].frame,
+		Frame::ExtCommand {
+			command: 0x22,
+			payload: vec![],
+			crc_valid: false
+		}
+	);
+	assert!(dissector.finish().is_empty());
+}
+
+#[test]
+fn simple_command_payload() {
+	let mut dissector = Dissector::new();
+	// a simple command with a payload, which only ends when the next command starts
+	assert!(dissector.feed(Duration::ZERO, Direction::Tx, &[0xc9, 0x36, 0xb8, 0x47, 0xa4, 0x01, 0x02]).is_empty());
+	let frames = dissector.feed(Duration::ZERO, Direction::Tx, &[0xc9, 0x36, 0xb8, 0x47, 0x58]);
+	assert_eq!(frames.len(), 1);
+	assert_eq!(frames[0].frame, Frame::SimpleCommand { command: 0xa4, payload: vec![0x01, 0x02] });
+	assert!(frames[0].to_string().ends_with("(device info): 01 02"), "{}", frames[0]);
+	// the last one ends with the data
+	let frames = dissector.finish();
+	assert_eq!(frames.len(), 1);
+	assert_eq!(frames[0].frame, Frame::SimpleCommand { command: 0x58, payload: vec![] });
+}
@@ -65,6 +65,13 @@ impl DeviceError {
 			Self::Other(_) => "Other",
 		}
 	}
+	/// Whether the device didn't respond in time, as opposed to responding incorrectly.
+	pub fn is_timeout(&self) -> bool {
+		match self {
+			Self::Io(err) | Self::Encde(encde::Error::IO(err)) => err.kind() == std::io::ErrorKind::TimedOut,
+			_ => false,
+		}
+	}
 }
 impl Error for DeviceError {
 	fn source(&self) -> Option<&(dyn Error + 'static)> {
@@ -26,6 +26,9 @@ pub struct WriteArgs {
 	pub timestamp: TimeStamp,
 	/// If specified, link to the specified file.
 	pub linked_file: Option<QualFileName>,
+	/// Gzip the data before sending it, which is faster over slow links such as the controller's radio. VEXos decompresses executables when loading them.
+	/// The size and CRC sent to the device are those of the compressed data.
+	pub compress: bool,
 }
 
 /// Extra arguments to `delete_file`.
@@ -3,19 +3,14 @@ use encde::{Decode, Encode};
 /// The channel that the device is functioning on.
 ///
 /// PROS CLI calls the file transfer channel the download channel.
-#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[repr(u8)]
 pub enum Channel {
+	#[default]
 	Pit = 0,
 	FileTransfer = 1,
 }
 
-impl Default for Channel {
-	fn default() -> Self {
-		Self::Pit
-	}
-}
-
 impl std::fmt::Display for Channel {
 	fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
 		let s = match self {
@@ -0,0 +1,11 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+/// Gzip the data, as VEXos accepts for executables.
+/// The output only depends on the input, so compressing the same data twice gives the same CRC.
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+	let mut encoder = GzEncoder::new(Vec::with_capacity(data.len() / 2), Compression::best());
+	encoder.write_all(data).expect("Writing to a Vec");
+	encoder.finish().expect("Writing to a Vec")
+}
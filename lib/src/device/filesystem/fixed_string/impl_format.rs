@@ -3,7 +3,10 @@ use std::fmt::{self, Formatter, Result};
 
 impl<const N: usize> fmt::Debug for FixedString<N> {
 	fn fmt(&self, formatter: &mut Formatter) -> Result {
-		write!(formatter, "FixedString<{}> \"{:?}\"", N, self.as_bytes())
+		match self.as_str() {
+			Ok(s) => write!(formatter, "FixedString<{}> {:?}", N, s),
+			Err(_) => write!(formatter, "FixedString<{}> \"{:?}\"", N, self.as_bytes()),
+		}
 	}
 }
 
@@ -1,8 +1,13 @@
+// The submodules of category, fixed_string and qual share names such as `error`, which the glob re-exports below make ambiguous.
+// Nothing refers to them through this module, and they stay public under their own modules.
+#![allow(ambiguous_glob_reexports)]
+
 use encde::{Decode, Encode};
 
 pub mod args;
 pub mod category;
 pub mod channel;
+pub mod compression;
 pub mod fixed_string;
 pub mod function;
 pub mod qual;
@@ -12,6 +17,7 @@ pub mod timestamp;
 pub use args::*;
 pub use category::*;
 pub use channel::*;
+pub use compression::*;
 pub use fixed_string::*;
 pub use function::*;
 pub use qual::*;
@@ -19,18 +25,14 @@ pub use target::*;
 pub use timestamp::*;
 
 /// What to do when the file transfer, specifically of an executable, completes.
-#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[repr(u8)]
 pub enum TransferCompleteAction {
+	#[default]
 	NoRun = 0b00,
 	RunImmediately = 0b01,
 	RunScreen = 0b11,
 }
-impl Default for TransferCompleteAction {
-	fn default() -> Self {
-		Self::NoRun
-	}
-}
 
 /// (The V5 is a 32-bit platform.)
 pub type Address = u32;
@@ -28,7 +28,6 @@ impl FromStr for QualFile {
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
 		let mut ret_category = Category::default();
 		let mut ret_name = s;
-		let ret_ty;
 
 		// if there is a category, the ret_name will be the part after the colon
 		// e.g., user:slot_1.ini
@@ -40,7 +39,7 @@ impl FromStr for QualFile {
 		// e.g., slot_1.ini
 
 		// then, using the name from the previous section, attempt to get the type
-		ret_ty = if let Some((_stem, ty)) = ret_name.rsplit_once('.') { ty } else { "bin" };
+		let ret_ty = if let Some((_stem, ty)) = ret_name.rsplit_once('.') { ty } else { "bin" };
 
 		Ok(Self {
 			common: QualFileName {
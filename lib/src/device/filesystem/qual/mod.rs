@@ -9,14 +9,14 @@ mod impl_from_str;
 pub use error::QualFileFromStrError;
 
 /// A qualified file name, that is, one with a category.
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub struct QualFileName {
 	pub category: Category,
 	pub name: FileName,
 }
 
 /// A qualified file, that is, one with a category and type.
-#[derive(Debug, Hash)]
+#[derive(Debug, Hash, Clone, Copy)]
 pub struct QualFile {
 	pub common: QualFileName,
 	pub ty: FileType,
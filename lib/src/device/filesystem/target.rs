@@ -1,17 +1,12 @@
 use encde::{Decode, Encode};
 
 /// The target of file transfers.
-#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 #[repr(u8)]
 pub enum Target {
 	Ddr = 0,
+	#[default]
 	Flash = 1,
 	/// Download only
 	Screen = 2,
 }
-
-impl Default for Target {
-	fn default() -> Self {
-		Self::Flash
-	}
-}
@@ -4,13 +4,13 @@ use std::fmt::{self, Display, Formatter};
 pub mod version;
 pub use version::{LongVersion, ShortVersion};
 
-#[derive(Decode, Clone, Copy)]
-pub struct BrainFlags(u8);
+#[derive(Decode, Clone, Copy, Debug)]
+pub struct BrainFlags(#[allow(dead_code)] u8);
 impl BrainFlags {
 	// empty
 }
 
-#[derive(Decode, Clone, Copy)]
+#[derive(Decode, Clone, Copy, Debug)]
 pub struct ControllerFlags(u8);
 impl ControllerFlags {
 	pub fn connected(&self) -> bool {
@@ -21,7 +21,7 @@ impl ControllerFlags {
 	}
 }
 
-#[derive(Decode, Clone, Copy)]
+#[derive(Decode, Clone, Copy, Debug)]
 #[repr(u8)]
 pub enum Product {
 	#[encde(wire_tag = 0x10)]
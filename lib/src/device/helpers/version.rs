@@ -4,7 +4,7 @@ use encde::{Decode, Encode};
 use std::cmp::{Ordering, PartialEq, PartialOrd};
 use std::fmt::{self, Debug, Display, Formatter};
 
-#[derive(Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 pub struct ShortVersion {
 	major: u8,
 	minor: u8,
@@ -24,7 +24,7 @@ impl Display for ShortVersion {
 	}
 }
 
-#[derive(Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[derive(Encode, Decode, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy)]
 pub struct LongVersion {
 	common: ShortVersion,
 	build_minor: u8,
@@ -48,7 +48,11 @@ impl Device {
 		self.rx_response_header()?;
 		self.rx_expect("echoed command", &Self::EXT_COMMAND)?;
 		// subtract echoed command byte, plus 16-bit CRC in rx_ext_command_footer
-		let payload_len = self.rx_ext_payload_length()? - 3;
+		let length = self.rx_ext_payload_length()?;
+		let payload_len = length.checked_sub(3).ok_or(DeviceError::Protocol(ProtocolError::BadLength {
+			entity: "extended response",
+			received_length: length,
+		}))?;
 		self.rx_expect("echoed actual command", &sent_command)?;
 		Ok(payload_len)
 	}
@@ -65,7 +69,11 @@ impl Device {
 	pub fn end_ext_command<T: Decode>(&mut self, sent_command: CommandId) -> Result<T> {
 		debug!("end extended command {:#02x}", sent_command);
 		// subtract response byte
-		let payload_len = self.rx_ext_command_header(sent_command)? - 1;
+		let length = self.rx_ext_command_header(sent_command)?;
+		let payload_len = length.checked_sub(1).ok_or(DeviceError::Protocol(ProtocolError::BadLength {
+			entity: "extended response payload",
+			received_length: length,
+		}))?;
 		let response_byte = self.rx_response_byte()?;
 		let raw_payload = self.rx_bytes(payload_len)?;
 		self.rx_ext_command_footer()?;
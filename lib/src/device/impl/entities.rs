@@ -9,23 +9,23 @@ use std::io::Read;
 
 impl Device {
 	pub const EXT_COMMAND: CommandId = 0x56;
+	pub const COMMAND_HEADER: [u8; 4] = [0xc9, 0x36, 0xb8, 0x47];
+	pub const RESPONSE_HEADER: [u8; 2] = [0xaa, 0x55];
 
 	/// A fixed byte-string used to signal that we would like to send a command.
 	pub fn tx_command_header(&mut self) -> Result<()> {
 		debug!("tx command header");
-		const HEADER: [u8; 4] = [0xc9, 0x36, 0xb8, 0x47];
-		self.tx_raw_data(&HEADER)?;
+		self.tx_raw_data(&Self::COMMAND_HEADER)?;
 		Ok(())
 	}
 	/// A fixed byte-string used to ensure that we didn't partially receive the previous command.
 	pub fn rx_response_header(&mut self) -> Result<()> {
 		debug!("rx response header");
 		let buf: [u8; 2] = self.rx_raw_data()?;
-		const EXPECTED: [u8; 2] = [0xaa, 0x55];
-		if buf != EXPECTED {
+		if buf != Self::RESPONSE_HEADER {
 			Err(DeviceError::Protocol(ProtocolError::WrongData {
 				entity: "Response header",
-				expected: EXPECTED.into(),
+				expected: Self::RESPONSE_HEADER.into(),
 				received: buf.into(),
 			}))
 		} else {
@@ -72,12 +72,12 @@ impl Device {
 		let raw = self.rx_simple_raw_payload()?;
 		Self::decode_from_data(&raw)
 	}
-	/// Has a maximum value of `u16::MAX` due to the encoding.
-	pub fn tx_vex_varint(&mut self, length: usize) -> Result<()> {
-		debug!("tx vex variable-length int: {}", length);
+	/// Encode a variable-length integer, which takes one byte below 0x80 and two otherwise, with the high bit of the first byte set.
+	/// Has a maximum value of 0x7fff due to the encoding.
+	pub fn encode_vex_varint(length: usize) -> Result<Vec<u8>> {
 		match length {
-			0..=0x7f => self.tx(&(length as u8)),
-			0x80..=0x7fff => self.tx(&[((length >> 8) | 0x80) as u8, (length & 0xff) as u8]),
+			0..=0x7f => Ok(vec![length as u8]),
+			0x80..=0x7fff => Ok(vec![((length >> 8) | 0x80) as u8, (length & 0xff) as u8]),
 			actual => Err(DeviceError::Protocol(ProtocolError::OutOfRange {
 				entity: "variable-length integer",
 				min: 0,
@@ -86,14 +86,28 @@ impl Device {
 			})),
 		}
 	}
+	/// See `encode_vex_varint`.
+	pub fn tx_vex_varint(&mut self, length: usize) -> Result<()> {
+		debug!("tx vex variable-length int: {}", length);
+		self.tx_raw_data(&Self::encode_vex_varint(length)?)
+	}
+	/// Decode a variable-length integer from the start of `data`, as encoded by `tx_vex_varint`.
+	/// Returns the value and the number of bytes it took up, or `None` if `data` ends partway through it.
+	pub fn decode_vex_varint(data: &[u8]) -> Option<(usize, usize)> {
+		let first = *data.first()? as usize;
+		if first & 0x80 == 0x80 {
+			Some((((first & 0x7f) << 8) + *data.get(1)? as usize, 2))
+		} else {
+			Some((first, 1))
+		}
+	}
 	/// See `tx_vex_varint` for comments.
 	pub fn rx_vex_varint(&mut self) -> Result<usize> {
-		let mut ret = self.rx::<u8>()? as usize;
-		if ret & 0x80 == 0x80 {
-			ret &= 0x7f;
-			ret <<= 8;
-			ret += self.rx::<u8>()? as usize;
+		let mut raw = vec![self.rx::<u8>()?];
+		if Self::decode_vex_varint(&raw).is_none() {
+			raw.push(self.rx::<u8>()?);
 		}
+		let (ret, _) = Self::decode_vex_varint(&raw).expect("A varint is at most two bytes");
 		debug!("rx vex variable-length int: {}", ret);
 		Ok(ret)
 	}
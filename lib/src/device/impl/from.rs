@@ -1,10 +1,55 @@
 use crate::device::discover::UploadableInfo;
-use crate::device::Device;
+use crate::device::{Device, UploadableType};
+use crate::transport::Transport;
 use log::debug;
 use std::path::Path;
 
 const SERIAL_BAUD: u32 = 115200;
 
+/// The serial port settings for V5 devices.
+pub(in crate::device) fn serial_port_builder(name: &str) -> serialport::SerialPortBuilder {
+	use serialport::*;
+	serialport::new(name, SERIAL_BAUD)
+		.parity(Parity::None)
+		.stop_bits(StopBits::One)
+		.data_bits(DataBits::Eight)
+		.flow_control(FlowControl::None)
+		.timeout(Device::DEFAULT_TIMEOUT)
+}
+
+impl Device {
+	/// Communicate with a device of the specified type over an arbitrary transport.
+	///
+	/// The transport's timeout is reset to `DEFAULT_TIMEOUT`.
+	pub fn from_transport(ty: UploadableType, transport: Box<dyn Transport>) -> crate::device::Result<Self> {
+		debug!("Using transport {} for V5 device of type {:?}", transport.name().as_deref().unwrap_or("(unknown)"), ty);
+		let mut ret = Device {
+			ty,
+			port: transport.into(),
+			retry_policy: Default::default(),
+			info: None,
+		};
+		ret.reset_timeout()?;
+		Ok(ret)
+	}
+	/// The inverse of `from_transport`, e.g., to wrap the transport in another one.
+	pub fn into_transport(self) -> (UploadableType, Box<dyn Transport>) {
+		(self.ty, self.port.into_inner())
+	}
+	/// The name of the transport, such as the path of the serial port.
+	pub fn name(&self) -> Option<String> {
+		self.port.port().name()
+	}
+	/// The name of the brain's user port, if it was found when the device was opened.
+	pub fn user_port(&self) -> Option<&str> {
+		self.info.as_ref()?.user_port.as_deref()
+	}
+	/// See `UploadableInfo::open_user_port`.
+	pub fn open_user_port(&self) -> crate::device::Result<Box<dyn Transport>> {
+		self.info.as_ref().ok_or(crate::device::DeviceError::Serial(crate::device::SerialError::NoDevice))?.open_user_port()
+	}
+}
+
 impl<'a> TryFrom<&'a Path> for Device {
 	type Error = <UploadableInfo as TryFrom<&'a Path>>::Error;
 	fn try_from(path: &'a Path) -> Result<Self, Self::Error> {
@@ -15,18 +60,12 @@ impl<'a> TryFrom<&'a Path> for Device {
 impl TryFrom<UploadableInfo> for Device {
 	type Error = serialport::Error;
 	fn try_from(info: UploadableInfo) -> Result<Self, Self::Error> {
-		use serialport::*;
 		debug!("Opening serial port {} for V5 device of type {:?}", &info.name, &info.device_type);
 		Ok(Device {
 			ty: info.device_type,
-			port: serialport::new(info.name, SERIAL_BAUD)
-				.parity(Parity::None)
-				.stop_bits(StopBits::One)
-				.data_bits(DataBits::Eight)
-				.flow_control(FlowControl::None)
-				.timeout(Self::DEFAULT_TIMEOUT)
-				.open()?
-				.into(),
+			port: serial_port_builder(&info.name).open()?.into(),
+			retry_policy: Default::default(),
+			info: Some(info),
 		})
 	}
 }
@@ -5,10 +5,15 @@ mod entities;
 mod file_transfer;
 mod from;
 pub mod public;
-mod receive;
+pub(in crate::device) mod receive;
+mod resync;
 mod screen_capture;
-mod send;
+pub(in crate::device) mod send;
+pub(in crate::device) mod shared;
 mod timeout;
 mod trivial;
 
-type CommandId = u8;
+pub type CommandId = u8;
+#[cfg(feature = "async")]
+pub(in crate::device) use file_transfer::pad;
+pub(in crate::device) use from::serial_port_builder;
@@ -1,144 +1,106 @@
 //! The public interface to the Device.
 
 use super::send as priv_send;
-use super::CommandId;
-use crate::device::{filesystem, helpers, receive, send};
-use crate::device::{Device, DeviceError, ProtocolError, ResponseByte, Result};
-use log::{debug, warn};
+use super::{shared, CommandId};
+use crate::device::{filesystem, receive, send};
+use crate::device::{Device, Result};
+use log::debug;
 
 impl Device {
 	pub fn device_info(&mut self) -> Result<receive::DeviceInfo> {
 		debug!("sending device info command");
-		self.simple_command_no_data(0xa4)
+		self.with_retries("device info", |dev| dev.simple_command_no_data(0xa4))
 	}
 
-	fn has_new_ext_dev_info(&mut self) -> Result<bool> {
-		let device_info = self.device_info()?;
-		Ok(match device_info.product {
-			helpers::Product::Brain(_) => device_info.version >= helpers::LongVersion::new(1, 0, 13, 0, 0),
-			helpers::Product::Controller(_) => false,
-		})
-	}
 	pub fn extended_device_info(&mut self) -> Result<receive::ExtendedDeviceInfo> {
 		debug!("sending extended device info command");
 		const COMMAND_ID: CommandId = 0x22;
-		if self.has_new_ext_dev_info()? {
-			self.ext_command_no_data::<receive::ExtendedDeviceInfoNew>(COMMAND_ID).map(receive::ExtendedDeviceInfo::from)
+		if shared::has_new_ext_dev_info(&self.device_info()?) {
+			self.with_retries("extended device info", |dev| dev.ext_command_no_data::<receive::ExtendedDeviceInfoNew>(COMMAND_ID))
+				.map(receive::ExtendedDeviceInfo::from)
 		} else {
-			self.ext_command_no_data::<receive::ExtendedDeviceInfo>(COMMAND_ID)
+			self.with_retries("extended device info", |dev| dev.ext_command_no_data::<receive::ExtendedDeviceInfo>(COMMAND_ID))
 		}
 	}
 
+	pub fn system_flags(&mut self) -> Result<receive::SystemFlags> {
+		debug!("sending system flags command");
+		self.with_retries("system flags", |dev| dev.ext_command_no_data::<receive::SystemFlags>(0x20))
+	}
+	/// Whether a program is running, according to `system_flags`.
+	pub fn program_running(&mut self) -> Result<bool> {
+		Ok(self.system_flags()?.current_program != 0)
+	}
+
 	/// `Ok(None)` is returned if the file does not exist.
 	pub fn get_file_metadata_by_name(&mut self, args: &send::FileMetadataByName) -> Result<Option<receive::FileMetadataByName>> {
 		debug!("sending get-file-metadata-by-name command");
-		let ret = self.ext_command_with_data::<_, receive::FileMetadataByName>(0x19, args);
-		match ret {
-			Ok(data) => Ok(Some(data)),
-			Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent | ResponseByte::ProgramFileError))) => Ok(None),
-			Err(err) => Err(err),
-		}
+		shared::not_found_as_none(self.with_retries("get-file-metadata-by-name", |dev| dev.ext_command_with_data::<_, receive::FileMetadataByName>(0x19, args)))
 	}
 	/// `Ok(None)` is returned if the file does not exist.
 	///
 	/// This is mostly used to either get the name of a file by its index, or to list the contents of a category.
 	/// The latter can also be done with `list_all_files`.
 	pub fn get_file_metadata_by_index(&mut self, index: filesystem::FileIndex) -> Result<Option<receive::FileMetadataByIndex>> {
-		let ret = self.ext_command_with_data::<_, receive::FileMetadataByIndex>(0x17, &send::FileMetadataByIndex::new(index));
-		match ret {
-			Ok(data) => Ok(Some(data)),
-			Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent | ResponseByte::ProgramFileError))) => Ok(None),
-			Err(err) => Err(err),
-		}
+		let args = send::FileMetadataByIndex::new(index);
+		shared::not_found_as_none(self.with_retries("get-file-metadata-by-index", |dev| dev.ext_command_with_data::<_, receive::FileMetadataByIndex>(0x17, &args)))
 	}
 
 	pub fn num_files(&mut self, category: filesystem::Category) -> Result<isize> {
 		debug!("sending num-files command");
-		self.ext_command_with_data::<_, receive::NumFiles>(0x16, &send::NumFiles::new(category)).map(|receive::NumFiles(num)| num as isize)
+		let args = send::NumFiles::new(category);
+		self.with_retries("num-files", |dev| dev.ext_command_with_data::<_, receive::NumFiles>(0x16, &args)).map(|receive::NumFiles(num)| num as isize)
 	}
 	pub fn list_all_files(&mut self, category: filesystem::Category) -> Result<Vec<receive::FileMetadataByIndex>> {
 		debug!("listing all files");
-		let num_files: usize = self.num_files(category)?.try_into().expect("The number of files was negative");
-		let num_files = if num_files > (u8::MAX as usize) {
-			warn!("There are too many files to list all of them; only listing the first {}", u8::MAX);
-			u8::MAX
-		} else {
-			num_files as u8
-		};
+		let num_files = shared::num_listable_files(self.num_files(category)?);
 		let mut ret = Vec::with_capacity(num_files as usize);
 		for i in 0..num_files {
-			ret.push(self.get_file_metadata_by_index(i)?.ok_or(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent)))?)
+			ret.push(self.get_file_metadata_by_index(i)?.ok_or_else(shared::not_found)?)
 		}
 		Ok(ret)
 	}
 
 	pub fn read_file_to_stream(&mut self, stream: &mut dyn std::io::Write, file: &filesystem::QualFile, args: &filesystem::ReadArgs) -> Result<()> {
 		debug!("reading file {}", file);
-		let (size, address) = match (args.size, args.address) {
-			(Some(size), Some(address)) => (size, address),
-			(maybe_size, maybe_address) => {
-				let file_metadata = self
-					.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common))?
-					.ok_or(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent)))?;
-				let size = maybe_size.unwrap_or(file_metadata.size);
-				let address = maybe_address.unwrap_or(file_metadata.address);
-				(size, address)
+		let (size, address) = match shared::read_extent(args, None) {
+			Some(extent) => extent,
+			None => {
+				let file_metadata = self.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common))?.ok_or_else(shared::not_found)?;
+				shared::read_extent(args, Some(&file_metadata)).expect("The metadata has both the size and the address")
 			}
 		};
-		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer {
-			function: filesystem::Function::Download,
-			target: args.target,
-			category: file.common.category,
-			overwrite: false,
-			size,
-			address,
-			crc: 0,
-			file_type: file.ty,
-			timestamp: Default::default(),
-			version: helpers::ShortVersion::new(1, 0, 0, 0),
-			name: file.common.name,
-		})?;
+		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer::download(file, args.target, size, address))?;
 		let crc = self.ft_read(stream, size, address, transfer_info.max_packet_size)?;
 		self.end_file_transfer(filesystem::TransferCompleteAction::default())?;
-		if !args.ignore_crc && crc != transfer_info.crc {
-			Err(DeviceError::Protocol(ProtocolError::InvalidCrc))
-		} else {
-			Ok(())
-		}
+		shared::check_read_crc(args, crc, &transfer_info)
 	}
 
 	/// Write to the device from the specified stream. You will need to also provide the size of the file and the CRC beforehand. If you don't want to calculate them yourself, you can use `write_file_from_slice`.
+	///
+	/// If `args.compress` is set, the stream has to be read in full to compress it, and `crc` is not used.
 	pub fn write_file_from_stream(&mut self, stream: &mut dyn std::io::Read, file: &filesystem::QualFile, size: filesystem::FileSize, crc: u32, args: &filesystem::WriteArgs) -> Result<()> {
+		if args.compress {
+			let mut data = Vec::with_capacity(size as usize);
+			std::io::Read::read_to_end(&mut std::io::Read::take(&mut *stream, size as u64), &mut data)?;
+			return self.write_file_from_slice(&data, file, args);
+		}
+		self.write_file_from_stream_as_is(stream, file, size, crc, args)
+	}
+	/// `write_file_from_stream` without compression.
+	fn write_file_from_stream_as_is(&mut self, stream: &mut dyn std::io::Read, file: &filesystem::QualFile, size: filesystem::FileSize, crc: u32, args: &filesystem::WriteArgs) -> Result<()> {
 		let address = match args.address {
 			Some(addr) => addr,
 			None => self.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common))?.map(|x| x.address).unwrap_or(filesystem::DEFAULT_ADDRESS),
 		};
 		self.set_transfer_channel(filesystem::Channel::FileTransfer)?;
-		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer {
-			function: filesystem::Function::Upload,
-			target: filesystem::Target::Flash,
-			category: file.common.category,
-			overwrite: args.overwrite,
-			size,
-			address,
-			crc,
-			file_type: file.ty,
-			timestamp: args.timestamp,
-			version: helpers::ShortVersion::new(1, 0, 0, 0),
-			name: file.common.name,
-		})?;
-		if transfer_info.file_size < size {
-			return Err(DeviceError::Protocol(ProtocolError::BadLength {
-				entity: "echoed length of file to write",
-				received_length: transfer_info.file_size as usize,
-			}));
-		}
+		let transfer_info = self.start_file_transfer(&priv_send::StartFileTransfer::upload(file, args, size, address, crc))?;
+		shared::check_upload_size(&transfer_info, size)?;
 		if let Some(ref linked_file) = args.linked_file {
 			self.ft_set_link(linked_file)?;
 		}
 		self.ft_write(stream, size, address, transfer_info.max_packet_size)?;
-		// a large file transfer can take a while to end, so set the timeout as such. A 500 KB file would result in a timeout of 10 seconds.
-		self.set_timeout(std::time::Duration::from_millis(std::cmp::max(size / 50, 1000) as u64))?;
+		self.set_timeout(shared::end_upload_timeout(size))?;
 		self.end_file_transfer(args.action)?;
 		self.reset_timeout()?;
 		self.set_transfer_channel(filesystem::Channel::Pit)?;
@@ -146,21 +108,14 @@ impl Device {
 	}
 	/// Write to a file from a slice. The size will be the size of the slice, and the CRC will be calculated for you.
 	pub fn write_file_from_slice(&mut self, data: &[u8], file: &filesystem::QualFile, args: &filesystem::WriteArgs) -> Result<()> {
-		let mut stream = encde::util::SliceReader::new(data);
-		let crc = *<u32 as crate::crc::CrcComputable>::update_crc(&mut 0u32, data);
-		let size: filesystem::FileSize = data.len().try_into().expect("Data to be written is too large");
-		self.write_file_from_stream(&mut stream, file, size, crc, args)
+		let (data, size, crc) = shared::prepare_upload(data, args);
+		let mut stream = encde::util::SliceReader::new(&data);
+		self.write_file_from_stream_as_is(&mut stream, file, size, crc, args)
 	}
 
 	pub fn delete_file(&mut self, file: &filesystem::QualFileName, args: &filesystem::DeleteArgs) -> Result<bool> {
 		let ret = self.ext_command_with_data::<_, ()>(0x1b, &priv_send::DeleteFile::new(file, args.include_linked));
-		let was_deleted = match ret {
-			Ok(_) => true,
-			Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent | ResponseByte::ProgramFileError))) => false,
-			Err(err) => {
-				return Err(err);
-			}
-		};
+		let was_deleted = shared::not_found_as_none(ret)?.is_some();
 		// I'm not convinced that this is necessary, but the PROS CLI includes it.
 		if was_deleted {
 			self.end_file_transfer(Default::default())?;
@@ -173,6 +128,13 @@ impl Device {
 		self.receive_screen_capture(output_stream)
 	}
 
+	/// Like `capture_screen`, but keeping the pixels in memory.
+	pub fn capture_screen_image(&mut self) -> Result<crate::device::screen::Framebuffer> {
+		let mut data = Vec::with_capacity(Self::SCREEN_TOTAL_SIZE);
+		self.capture_screen(&mut data)?;
+		Ok(crate::device::screen::Framebuffer::from_bgra(data))
+	}
+
 	pub fn execute_file(&mut self, file: &filesystem::QualFileName) -> Result<()> {
 		self.ext_command_with_data::<_, ()>(0x18, &priv_send::ExecuteFile::start(file))
 	}
@@ -180,4 +142,37 @@ impl Device {
 	pub fn stop_execution(&mut self) -> Result<()> {
 		self.ext_command_with_data::<_, ()>(0x18, &priv_send::ExecuteFile::stop())
 	}
+
+	/// Send an arbitrary simple command with an arbitrary payload, returning the response as-is.
+	///
+	/// Intended for investigating undocumented commands; prefer the specific methods otherwise.
+	pub fn raw_simple_command(&mut self, command_id: CommandId, data: &[u8]) -> Result<receive::RawResponse> {
+		debug!("sending raw simple command {:#02x}", command_id);
+		self.begin_simple_command(command_id)?;
+		self.tx_raw_data(data)?;
+		self.rx_response_header()?;
+		self.rx_echoed_command(command_id)?;
+		Ok(receive::RawResponse {
+			response_byte: None,
+			payload: self.rx_simple_raw_payload()?,
+			crc_valid: None,
+		})
+	}
+	/// Send an arbitrary extended command with an arbitrary payload, returning the response as-is.
+	/// Unlike other commands, neither a NACK nor an invalid CRC is an error.
+	///
+	/// Intended for investigating undocumented commands; prefer the specific methods otherwise.
+	pub fn raw_ext_command(&mut self, command_id: CommandId, data: &[u8]) -> Result<receive::RawResponse> {
+		debug!("sending raw extended command {:#02x}", command_id);
+		self.begin_ext_command(command_id, data)?;
+		let payload_len = self.rx_ext_command_header(command_id)?;
+		let mut payload = self.rx_bytes(payload_len)?;
+		let crc_valid = self.port.end_rx_crc()?;
+		let response_byte = if payload.is_empty() { None } else { Some(payload.remove(0)) };
+		Ok(receive::RawResponse {
+			response_byte,
+			payload,
+			crc_valid: Some(crc_valid),
+		})
+	}
 }
@@ -3,7 +3,7 @@
 use crate::device::filesystem::{FileSize, PacketSize};
 use encde::Decode;
 
-#[derive(Decode)]
+#[derive(Decode, Debug)]
 pub struct StartFileTransfer {
 	/// The maximum packet size for following read or write commands.
 	pub max_packet_size: PacketSize,
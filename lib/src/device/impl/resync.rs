@@ -0,0 +1,53 @@
+//! Getting back in step with the device after something went wrong partway through a command.
+
+use crate::device::{Device, DeviceError, Result, RetryPolicy};
+use log::debug;
+use std::io::Read;
+use std::time::Duration;
+
+impl Device {
+	/// How long the input has to be quiet before `drain_input` considers it drained.
+	pub const DRAIN_TIMEOUT: Duration = Duration::from_millis(50);
+
+	/// Discard everything the device has sent that we haven't read yet, such as the rest of a malformed response or a response that arrived after we timed out.
+	/// Returns the number of bytes discarded.
+	pub fn drain_input(&mut self) -> Result<usize> {
+		let old_timeout = self.timeout();
+		self.set_timeout(Self::DRAIN_TIMEOUT)?;
+		let mut drained = 0;
+		let mut buf = [0u8; 64];
+		let ret = loop {
+			match self.port.read(&mut buf) {
+				Ok(0) => break Ok(()),
+				Ok(amount) => drained += amount,
+				Err(err) => {
+					let err = DeviceError::from(err);
+					break if err.is_timeout() { Ok(()) } else { Err(err) };
+				}
+			}
+		};
+		self.set_timeout(old_timeout)?;
+		debug!("drained {} bytes of input", drained);
+		ret.map(|()| drained)
+	}
+
+	pub fn retry_policy(&self) -> RetryPolicy {
+		self.retry_policy
+	}
+	pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+		self.retry_policy = policy;
+	}
+	/// Run an operation, draining the input and running it again if it fails in a way the retry policy considers transient.
+	/// The operation must be safe to repeat, including when the device received it the first time but its response was lost.
+	pub fn with_retries<T>(&mut self, what: &str, mut operation: impl FnMut(&mut Self) -> Result<T>) -> Result<T> {
+		let mut retries = 0;
+		loop {
+			match operation(self) {
+				Err(err) if self.retry_policy.should_retry(what, &mut retries, &err) => {
+					self.drain_input()?;
+				}
+				ret => return ret,
+			}
+		}
+	}
+}
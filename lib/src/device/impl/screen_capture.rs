@@ -12,22 +12,8 @@ impl Device {
 	}
 	pub fn receive_screen_capture(&mut self, output_stream: &mut dyn std::io::Write) -> Result<()> {
 		self.set_transfer_channel(fs::Channel::FileTransfer)?;
-		self.read_file_to_stream(
-			output_stream,
-			&fs::QualFile {
-				common: fs::QualFileName {
-					category: fs::Category::SYSTEM,
-					name: fs::FileName::default(),
-				},
-				ty: fs::FileType::default(),
-			},
-			&fs::ReadArgs {
-				target: fs::Target::Screen,
-				address: Some(0),
-				size: Some(Self::SCREEN_TOTAL_SIZE as u32),
-				ignore_crc: true,
-			},
-		)?;
+		let (screen, args) = super::shared::screen_capture_file();
+		self.read_file_to_stream(output_stream, &screen, &args)?;
 		self.set_transfer_channel(fs::Channel::Pit)?;
 		Ok(())
 	}
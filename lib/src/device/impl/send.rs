@@ -2,10 +2,10 @@
 
 use crate::device::filesystem::*;
 use crate::device::helpers::ShortVersion;
-use encde::Encode;
+use encde::{Decode, Encode};
 
 /// Start a file transfer.
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct StartFileTransfer {
 	pub function: Function,
 	pub target: Target,
@@ -25,15 +25,50 @@ pub struct StartFileTransfer {
 	pub name: FileName,
 }
 
+impl StartFileTransfer {
+	/// Start reading `size` bytes of `file` from `address`.
+	pub fn download(file: &QualFile, target: Target, size: FileSize, address: Address) -> Self {
+		Self {
+			function: Function::Download,
+			target,
+			category: file.common.category,
+			overwrite: false,
+			size,
+			address,
+			crc: 0,
+			file_type: file.ty,
+			timestamp: Default::default(),
+			version: ShortVersion::new(1, 0, 0, 0),
+			name: file.common.name,
+		}
+	}
+	/// Start writing `size` bytes with the given CRC to `file` at `address`.
+	pub fn upload(file: &QualFile, args: &WriteArgs, size: FileSize, address: Address, crc: u32) -> Self {
+		Self {
+			function: Function::Upload,
+			target: Target::Flash,
+			category: file.common.category,
+			overwrite: args.overwrite,
+			size,
+			address,
+			crc,
+			file_type: file.ty,
+			timestamp: args.timestamp,
+			version: ShortVersion::new(1, 0, 0, 0),
+			name: file.common.name,
+		}
+	}
+}
+
 /// Read a packet at `address` with size `size`.
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct FileTransferRead {
 	pub address: Address,
 	/// Must be less than the max packet size received when starting the transfer.
 	pub size: PacketSize,
 }
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct DeleteFile {
 	pub category: Category,
 	/// The MSB indicates whether to also delete the linked file (if the file has one).
@@ -52,7 +87,7 @@ impl DeleteFile {
 }
 
 /// Set the link of a file. For unknown reasons, this can only be set during a file transfer.
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct FileTransferSetLink {
 	pub linked_category: Category,
 	/// Currently unused.
@@ -70,7 +105,7 @@ impl FileTransferSetLink {
 	}
 }
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct FileTransferSetChannel {
 	/// Currently unused.
 	options: u8,
@@ -86,7 +121,7 @@ impl FileTransferSetChannel {
 	}
 }
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct ExecuteFile {
 	pub category: Category,
 	options: u8,
@@ -0,0 +1,127 @@
+//! The parts of commands that don't involve I/O: building payloads and interpreting responses.
+//! These are shared with `AsyncDevice`, so that it only has to do the sending and receiving differently.
+
+use super::receive as priv_receive;
+use crate::device::{filesystem, helpers, receive, Device, DeviceError, ProtocolError, ResponseByte, Result};
+use log::warn;
+use std::borrow::Cow;
+use std::time::Duration;
+
+/// Whether the device uses the newer format for the extended device info.
+pub fn has_new_ext_dev_info(device_info: &receive::DeviceInfo) -> bool {
+	match device_info.product {
+		helpers::Product::Brain(_) => device_info.version >= helpers::LongVersion::new(1, 0, 13, 0, 0),
+		helpers::Product::Controller(_) => false,
+	}
+}
+
+/// Turn the NACKs that mean a file doesn't exist into `Ok(None)`.
+pub fn not_found_as_none<T>(result: Result<T>) -> Result<Option<T>> {
+	match result {
+		Ok(data) => Ok(Some(data)),
+		Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent | ResponseByte::ProgramFileError))) => Ok(None),
+		Err(err) => Err(err),
+	}
+}
+
+/// The error for a file that was expected to exist.
+pub fn not_found() -> DeviceError {
+	DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enoent))
+}
+
+/// How many of the files in a category can be listed, since they're listed by a one-byte index.
+pub fn num_listable_files(num_files: isize) -> u8 {
+	let num_files: usize = num_files.try_into().expect("The number of files was negative");
+	if num_files > (u8::MAX as usize) {
+		warn!("There are too many files to list all of them; only listing the first {}", u8::MAX);
+		u8::MAX
+	} else {
+		num_files as u8
+	}
+}
+
+/// The size and address to read, or `None` if the file's metadata is needed to fill them in.
+pub fn read_extent(args: &filesystem::ReadArgs, metadata: Option<&receive::FileMetadataByName>) -> Option<(filesystem::FileSize, filesystem::Address)> {
+	Some((args.size.or_else(|| metadata.map(|x| x.size))?, args.address.or_else(|| metadata.map(|x| x.address))?))
+}
+
+/// Compare the CRC of the data that was read to the one the device gave when starting the transfer.
+pub fn check_read_crc(args: &filesystem::ReadArgs, crc: u32, transfer_info: &priv_receive::StartFileTransfer) -> Result<()> {
+	if !args.ignore_crc && crc != transfer_info.crc {
+		Err(DeviceError::Protocol(ProtocolError::InvalidCrc))
+	} else {
+		Ok(())
+	}
+}
+
+/// Compress the data if requested, returning it along with its size and CRC.
+pub fn prepare_upload<'a>(data: &'a [u8], args: &filesystem::WriteArgs) -> (Cow<'a, [u8]>, filesystem::FileSize, u32) {
+	let data = if args.compress { Cow::Owned(filesystem::gzip(data)) } else { Cow::Borrowed(data) };
+	let crc = *<u32 as crate::crc::CrcComputable>::update_crc(&mut 0u32, &data);
+	let size: filesystem::FileSize = data.len().try_into().expect("Data to be written is too large");
+	(data, size, crc)
+}
+
+/// Check that the device is prepared to receive the whole file.
+pub fn check_upload_size(transfer_info: &priv_receive::StartFileTransfer, size: filesystem::FileSize) -> Result<()> {
+	if transfer_info.file_size < size {
+		Err(DeviceError::Protocol(ProtocolError::BadLength {
+			entity: "echoed length of file to write",
+			received_length: transfer_info.file_size as usize,
+		}))
+	} else {
+		Ok(())
+	}
+}
+
+/// A large file transfer can take a while to end, so the timeout for ending it depends on the size. A 500 KB file results in a timeout of 10 seconds.
+pub fn end_upload_timeout(size: filesystem::FileSize) -> Duration {
+	Duration::from_millis(std::cmp::max(size / 50, 1000) as u64)
+}
+
+/// Split a transfer into packets, giving the address and size of each.
+pub fn packets(size: filesystem::FileSize, base_address: filesystem::Address, max_packet_size: filesystem::PacketSize) -> impl Iterator<Item = (filesystem::Address, usize)> {
+	(0..size)
+		.step_by(max_packet_size.max(1) as usize)
+		.map(move |offset| (base_address + offset, std::cmp::min(size - offset, max_packet_size as filesystem::FileSize) as usize))
+}
+
+/// Check the length of a file transfer read response, which is the address followed by the padded data.
+pub fn check_ft_read_length(length: usize, amount_to_read: filesystem::PacketSize) -> Result<()> {
+	let address_size = std::mem::size_of::<filesystem::Address>();
+	if length.checked_sub(address_size) != Some(amount_to_read as usize) {
+		Err(DeviceError::Protocol(ProtocolError::BadLength {
+			entity: "file transfer read packet",
+			received_length: length.saturating_sub(address_size),
+		}))
+	} else {
+		Ok(())
+	}
+}
+
+/// The payload of a file transfer write: the address followed by the data, padded to a multiple of 4.
+pub fn ft_write_payload(data: &[u8], base_address: filesystem::Address) -> Result<Vec<u8>> {
+	let amount_to_write: filesystem::PacketSize = data.len().try_into().expect("Buffer is too large to write in one packet");
+	let mut payload = encde::util::encode_to_vec(&base_address)?;
+	payload.extend_from_slice(data);
+	payload.resize(payload.len() + super::file_transfer::pad(amount_to_write) as usize - data.len(), 0);
+	Ok(payload)
+}
+
+/// The screen is read like a file.
+pub fn screen_capture_file() -> (filesystem::QualFile, filesystem::ReadArgs) {
+	let file = filesystem::QualFile {
+		common: filesystem::QualFileName {
+			category: filesystem::Category::SYSTEM,
+			name: filesystem::FileName::default(),
+		},
+		ty: filesystem::FileType::default(),
+	};
+	let args = filesystem::ReadArgs {
+		target: filesystem::Target::Screen,
+		address: Some(0),
+		size: Some(Device::SCREEN_TOTAL_SIZE as u32),
+		ignore_crc: true,
+	};
+	(file, args)
+}
@@ -1,4 +1,4 @@
-use crate::device::{Device, DeviceError, Result};
+use crate::device::{Device, Result};
 use std::time::Duration;
 
 impl Device {
@@ -8,7 +8,7 @@ impl Device {
 		self.port.port().timeout()
 	}
 	pub fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
-		self.port.port_mut().set_timeout(timeout).map_err(DeviceError::from)
+		self.port.port_mut().set_timeout(timeout)
 	}
 	pub fn reset_timeout(&mut self) -> Result<()> {
 		self.set_timeout(Self::DEFAULT_TIMEOUT)
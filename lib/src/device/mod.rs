@@ -1,23 +1,34 @@
 use std::fmt::{self, Debug, Formatter};
 
+#[cfg(feature = "async")]
+pub mod asynchronous;
 pub mod discover;
+pub mod dissect;
 pub mod error;
 pub mod filesystem;
-mod helpers;
+pub mod helpers;
 // Maybe you're looking for this? All the actual code is in here.
 mod r#impl;
+pub mod probe;
 pub mod receive;
 pub mod response_byte;
+pub mod retry;
+pub mod screen;
 pub mod send;
 
 pub use discover::{UploadableInfo, UploadableType};
 pub use error::*;
+pub use r#impl::CommandId;
 pub use response_byte::ResponseByte;
+pub use retry::RetryPolicy;
 
 pub struct Device {
 	ty: UploadableType,
 	/// The serial port used to communicate with the device.
 	port: crate::crc::CrcSerialPort,
+	retry_policy: RetryPolicy,
+	/// How the device was discovered, if it was opened from a serial port. Used to find its user port.
+	info: Option<UploadableInfo>,
 }
 
 impl Debug for Device {
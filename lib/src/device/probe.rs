@@ -0,0 +1,108 @@
+//! Discovering which extended commands a device supports, by sending each one and seeing what comes back.
+
+use super::helpers::{LongVersion, Product};
+use super::{CommandId, Device, DeviceError, ResponseByte, Result};
+use log::debug;
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+	Ack,
+	Nack(ResponseByte),
+	/// A response byte that isn't in `ResponseByte`.
+	UnknownResponse(u8),
+	/// The device didn't respond, or stopped partway through.
+	Timeout,
+	/// The response wasn't a valid extended response to the command we sent.
+	Malformed(String),
+}
+
+impl Display for Outcome {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Ack => formatter.write_str("ack"),
+			Self::Nack(response_byte) => write!(formatter, "nack {:#04x} ({})", *response_byte as u8, response_byte),
+			Self::UnknownResponse(byte) => write!(formatter, "unknown response {:#04x}", byte),
+			Self::Timeout => formatter.write_str("timeout"),
+			Self::Malformed(reason) => write!(formatter, "malformed ({})", reason),
+		}
+	}
+}
+
+/// Extended commands known to change the device's state, such as by writing, deleting, or running files, or by switching channels.
+/// Probing these with an arbitrary payload is risky, so they should be skipped unless asked for.
+pub const STATE_CHANGING: &[CommandId] = &[
+	0x10, // set channel
+	0x11, // start file transfer
+	0x12, // end file transfer
+	0x13, // write file transfer
+	0x15, // set link
+	0x18, // execute or stop a program
+	0x1a, // set file metadata
+	0x1b, // delete a file
+	0x1e, // clean up the filesystem
+	0x1f, // erase the filesystem
+	0x2f, // write a system key
+];
+
+/// The outcomes of probing a device, along with what's needed to tell devices apart.
+#[derive(Debug, Clone)]
+pub struct Report {
+	pub product: Product,
+	pub version: LongVersion,
+	pub outcomes: BTreeMap<CommandId, Outcome>,
+}
+
+impl Report {
+	pub fn new(product: Product, version: LongVersion) -> Self {
+		Self { product, version, outcomes: BTreeMap::new() }
+	}
+	/// Reports are keyed by product and firmware version, so that they can be compared across versions.
+	/// A partial report, from a probe that failed partway through, gets its own name so that it doesn't replace a complete one.
+	pub fn file_name(&self, partial: bool) -> String {
+		let product = match self.product {
+			Product::Brain(_) => "brain",
+			Product::Controller(_) => "controller",
+		};
+		format!("{}-{}{}.txt", product, self.version, if partial { ".partial" } else { "" })
+	}
+}
+
+/// One line per command, sorted by command ID, so that reports can be compared with `diff`.
+impl Display for Report {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		writeln!(formatter, "# {} {}", self.product, self.version)?;
+		for (command_id, outcome) in self.outcomes.iter() {
+			writeln!(formatter, "{:#04x} {}", command_id, outcome)?;
+		}
+		Ok(())
+	}
+}
+
+impl Device {
+	/// Send an extended command and classify the response.
+	/// If the response was anything but an ACK or NACK, the input is drained so that the next command starts cleanly.
+	///
+	/// Errors are only returned if the link itself failed, e.g. if the device was disconnected.
+	pub fn probe_ext_command(&mut self, command_id: CommandId, payload: &[u8]) -> Result<Outcome> {
+		debug!("probing extended command {:#04x}", command_id);
+		let outcome = match self.raw_ext_command(command_id, payload) {
+			Ok(response) if response.crc_valid == Some(false) => Outcome::Malformed("invalid CRC".to_owned()),
+			Ok(response) => match (response.response_byte, response.known_response_byte()) {
+				(_, Some(ResponseByte::Ack)) => Outcome::Ack,
+				(_, Some(response_byte)) => Outcome::Nack(response_byte),
+				(Some(byte), None) => Outcome::UnknownResponse(byte),
+				(None, None) => Outcome::Malformed("no response byte".to_owned()),
+			},
+			Err(err) if err.is_timeout() => Outcome::Timeout,
+			Err(DeviceError::Protocol(err)) => Outcome::Malformed(err.to_string()),
+			Err(DeviceError::Encde(err)) => Outcome::Malformed(err.to_string()),
+			Err(err) => return Err(err),
+		};
+		if !matches!(outcome, Outcome::Ack | Outcome::Nack(_)) {
+			self.drain_input()?;
+		}
+		Ok(outcome)
+	}
+}
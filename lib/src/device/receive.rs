@@ -4,14 +4,14 @@ use super::filesystem::{Address, Category, FileIndex, FileName, FileSize, FileTy
 use super::helpers::{LongVersion, Product, ShortVersion, SystemId};
 use encde::Decode;
 
-#[derive(Decode)]
+#[derive(Decode, Debug)]
 pub struct DeviceInfo {
 	pub version: LongVersion,
 	#[encde(pad_after = 1)]
 	pub product: Product,
 }
 
-#[derive(Decode)]
+#[derive(Decode, Debug)]
 pub struct ExtendedDeviceInfo {
 	#[encde(pad_before = 1)]
 	pub system_version: ShortVersion,
@@ -24,7 +24,7 @@ pub struct ExtendedDeviceInfo {
 	pub system_id: SystemId,
 }
 
-#[derive(Decode)]
+#[derive(Decode, Debug)]
 pub struct ExtendedDeviceInfoNew {
 	pub common: ExtendedDeviceInfo,
 	/// This data is ignored by PROS CLI so there's no way to know what it actually is.
@@ -38,6 +38,18 @@ impl From<ExtendedDeviceInfoNew> for ExtendedDeviceInfo {
 	}
 }
 
+/// The layout follows community documentation of the protocol; only `current_program` is relied on.
+#[derive(Decode, Debug)]
+pub struct SystemFlags {
+	/// Meaning mostly unknown
+	pub flags: u32,
+	pub battery_percent: u8,
+	pub controller_battery_percent: u8,
+	pub partner_controller_battery_percent: u8,
+	/// The slot of the running program, or 0 if no program is running.
+	pub current_program: u8,
+}
+
 #[derive(Decode, Debug)]
 pub struct FileMetadataByName {
 	/// `Category::None` if the file has no link.
@@ -76,5 +88,23 @@ pub struct FileMetadataByIndex {
 	pub name: FileName,
 }
 
-#[derive(Decode)]
+#[derive(Decode, Debug)]
 pub struct NumFiles(pub i16);
+
+/// The undecoded response to a command sent with `Device::raw_simple_command` or `Device::raw_ext_command`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RawResponse {
+	/// Simple commands have no response byte.
+	/// Neither do some extended commands, notably file transfer reads, in which case this is the first byte of the payload.
+	pub response_byte: Option<u8>,
+	pub payload: Vec<u8>,
+	/// Simple commands have no CRC.
+	pub crc_valid: Option<bool>,
+}
+
+impl RawResponse {
+	/// The response byte, if there is one and it's one we know about.
+	pub fn known_response_byte(&self) -> Option<super::ResponseByte> {
+		self.response_byte.and_then(|byte| encde::util::decode_from_entire_slice(&[byte]).ok())
+	}
+}
@@ -0,0 +1,49 @@
+//! Recovering from transient errors, such as responses garbled by a flaky connection.
+
+use super::{DeviceError, ProtocolError, ResponseByte};
+
+/// How a `Device` retries commands whose response was garbled or missing.
+///
+/// Only commands that are safe to repeat are retried: queries, and individual file transfer packets, which are addressed explicitly.
+/// Before each retry, any remaining input is discarded with `Device::drain_input`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+	/// How many times to resend a command after the first attempt fails. Zero disables retrying.
+	pub max_retries: u32,
+	/// Whether to retry when the device doesn't respond in time. Otherwise, only garbled responses are retried.
+	pub retry_timeouts: bool,
+}
+
+impl RetryPolicy {
+	pub const NEVER: Self = Self { max_retries: 0, retry_timeouts: false };
+	/// What `Default` gives.
+	pub const DEFAULT: Self = Self { max_retries: 3, retry_timeouts: true };
+
+	/// Whether the error could plausibly go away by sending the same command again.
+	pub fn is_transient(&self, err: &DeviceError) -> bool {
+		match err {
+			DeviceError::Protocol(ProtocolError::WrongData { .. } | ProtocolError::BadLength { .. } | ProtocolError::InvalidCrc | ProtocolError::Nack(ResponseByte::ReceivedCrcError)) => true,
+			err if err.is_timeout() => self.retry_timeouts,
+			// a response that doesn't decode is most likely corrupted
+			DeviceError::Encde(encde::Error::IO(_)) => false,
+			DeviceError::Encde(_) => true,
+			_ => false,
+		}
+	}
+	/// Whether to retry after `err`, given how many retries there have been so far. If so, the retry is counted and logged.
+	pub(crate) fn should_retry(&self, what: &str, retries: &mut u32, err: &DeviceError) -> bool {
+		if *retries < self.max_retries && self.is_transient(err) {
+			*retries += 1;
+			log::warn!("{} failed, retrying ({}/{}): {}", what, retries, self.max_retries, err);
+			true
+		} else {
+			false
+		}
+	}
+}
+
+impl Default for RetryPolicy {
+	fn default() -> Self {
+		Self::DEFAULT
+	}
+}
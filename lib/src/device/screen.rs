@@ -0,0 +1,154 @@
+//! Screen captures as pixels, for when a stream of raw data isn't convenient.
+
+use super::Device;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// A pixel, with its channels in the order the brain stores them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pixel {
+	pub blue: u8,
+	pub green: u8,
+	pub red: u8,
+	pub alpha: u8,
+}
+
+/// A rectangle of pixels, written `X,Y,WIDTH,HEIGHT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+	pub x: usize,
+	pub y: usize,
+	pub width: usize,
+	pub height: usize,
+}
+
+impl Rect {
+	/// The exclusive right and bottom edges, or `None` if they don't fit in a `usize`.
+	fn end(&self) -> Option<(usize, usize)> {
+		Some((self.x.checked_add(self.width)?, self.y.checked_add(self.height)?))
+	}
+	/// Whether the pixel is within this. A rectangle that overflows contains nothing.
+	pub fn contains(&self, x: usize, y: usize) -> bool {
+		self.end().is_some_and(|(right, bottom)| (self.x..right).contains(&x) && (self.y..bottom).contains(&y))
+	}
+	/// Whether all of `other` is within this. Rectangles that overflow are never enclosed, nor enclose anything.
+	pub fn encloses(&self, other: &Rect) -> bool {
+		match (self.end(), other.end()) {
+			(Some((right, bottom)), Some((other_right, other_bottom))) => other.x >= self.x && other.y >= self.y && other_right <= right && other_bottom <= bottom,
+			_ => false,
+		}
+	}
+}
+
+impl Display for Rect {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		write!(formatter, "{},{},{},{}", self.x, self.y, self.width, self.height)
+	}
+}
+
+#[derive(Debug)]
+pub struct RectFromStrError;
+
+impl Display for RectFromStrError {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		write!(formatter, "expected four numbers X,Y,WIDTH,HEIGHT")
+	}
+}
+
+impl std::error::Error for RectFromStrError {}
+
+impl FromStr for Rect {
+	type Err = RectFromStrError;
+	fn from_str(raw: &str) -> Result<Self, Self::Err> {
+		let parts = raw.split(',').map(|part| part.trim().parse::<usize>()).collect::<Result<Vec<_>, _>>().map_err(|_| RectFromStrError)?;
+		match parts[..] {
+			[x, y, width, height] => Ok(Self { x, y, width, height }),
+			_ => Err(RectFromStrError),
+		}
+	}
+}
+
+/// The whole framebuffer, as captured from the brain.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Framebuffer {
+	/// `Device::SCREEN_HEIGHT` rows of `Device::SCREEN_WIDTH` pixels in BGRA.
+	data: Vec<u8>,
+}
+
+impl Framebuffer {
+	/// All of the framebuffer, including the columns past the right edge of the screen.
+	pub const FULL: Rect = Rect {
+		x: 0,
+		y: 0,
+		width: Device::SCREEN_WIDTH,
+		height: Device::SCREEN_HEIGHT,
+	};
+	/// The part of the framebuffer that is shown on the screen.
+	pub const VISIBLE: Rect = Rect {
+		x: 0,
+		y: 0,
+		width: Device::ACTUAL_SCREEN_WIDTH,
+		height: Device::SCREEN_HEIGHT,
+	};
+
+	/// Panics if the data isn't `Device::SCREEN_TOTAL_SIZE` bytes.
+	pub fn from_bgra(data: Vec<u8>) -> Self {
+		assert_eq!(data.len(), Device::SCREEN_TOTAL_SIZE, "Wrong framebuffer size");
+		Self { data }
+	}
+	/// The raw data, as `Device::capture_screen` outputs it.
+	pub fn bgra(&self) -> &[u8] {
+		&self.data
+	}
+	pub fn into_bgra(self) -> Vec<u8> {
+		self.data
+	}
+
+	/// Panics if the pixel is outside the framebuffer.
+	pub fn pixel(&self, x: usize, y: usize) -> Pixel {
+		assert!(Self::FULL.contains(x, y), "Pixel out of bounds");
+		let start = (y * Device::SCREEN_WIDTH + x) * Device::SCREEN_CHANNELS;
+		let [blue, green, red, alpha]: [u8; 4] = self.data[start..start + 4].try_into().unwrap();
+		Pixel { blue, green, red, alpha }
+	}
+
+	/// The rows of pixels within `rect`, each converted with `convert` and concatenated.
+	///
+	/// Panics if `rect` is not within the framebuffer.
+	fn region<const N: usize>(&self, rect: Rect, convert: impl Fn(&[u8]) -> [u8; N]) -> Vec<u8> {
+		assert!(Self::FULL.encloses(&rect), "Region {} is outside the framebuffer", rect);
+		let mut ret = Vec::with_capacity(rect.width * rect.height * N);
+		for y in rect.y..rect.y + rect.height {
+			let row_start = (y * Device::SCREEN_WIDTH + rect.x) * Device::SCREEN_CHANNELS;
+			for pixel in self.data[row_start..row_start + rect.width * Device::SCREEN_CHANNELS].chunks_exact(Device::SCREEN_CHANNELS) {
+				ret.extend_from_slice(&convert(pixel));
+			}
+		}
+		ret
+	}
+	/// The pixels within `rect` in BGRA, row by row. Panics if `rect` is not within the framebuffer.
+	pub fn bgra_region(&self, rect: Rect) -> Vec<u8> {
+		self.region(rect, |pixel| [pixel[0], pixel[1], pixel[2], pixel[3]])
+	}
+	/// The pixels within `rect` in RGB, row by row. Panics if `rect` is not within the framebuffer.
+	pub fn rgb_region(&self, rect: Rect) -> Vec<u8> {
+		self.region(rect, |pixel| [pixel[2], pixel[1], pixel[0]])
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::Rect;
+
+	#[test]
+	fn overflow() {
+		let huge = Rect { x: usize::MAX, y: 0, width: 2, height: 1 };
+		let full = Rect { x: 0, y: 0, width: 480, height: 272 };
+		assert!(!huge.contains(usize::MAX, 0));
+		assert!(!huge.contains(0, 0));
+		assert!(!full.encloses(&huge));
+		assert!(!huge.encloses(&full));
+		assert!(full.encloses(&Rect { x: 10, y: 10, width: 470, height: 262 }));
+		assert!(!full.encloses(&Rect { x: 10, y: 10, width: 471, height: 262 }));
+	}
+}
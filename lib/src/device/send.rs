@@ -1,9 +1,9 @@
 //! Payloads to be sent with commands. These are public to the crate, as opposed to `r#impl::send` which is private to the `Device`.
 
 use super::filesystem::{Category, FileIndex, FileName, QualFileName};
-use encde::Encode;
+use encde::{Decode, Encode};
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct FileMetadataByName {
 	pub category: Category,
 	// Currently unused.
@@ -21,7 +21,7 @@ impl FileMetadataByName {
 	}
 }
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct FileMetadataByIndex {
 	pub index: FileIndex,
 	// Currently unused.
@@ -34,7 +34,7 @@ impl FileMetadataByIndex {
 	}
 }
 
-#[derive(Encode)]
+#[derive(Encode, Decode, Debug)]
 pub struct NumFiles {
 	pub category: Category,
 	// Currently unused.
@@ -0,0 +1,69 @@
+//! The emulated brain's filesystem.
+
+use crate::crc::CrcComputable;
+use crate::device::filesystem::{Address, Category, FileSize, FileType, QualFileName, DEFAULT_ADDRESS};
+use crate::device::helpers::ShortVersion;
+
+/// A file stored on the emulated brain.
+#[derive(Debug, Clone)]
+pub struct VirtualFile {
+	pub name: QualFileName,
+	pub file_type: FileType,
+	pub address: Address,
+	/// Seconds since 1 January 2000, as stored on the device.
+	pub timestamp: u32,
+	pub version: ShortVersion,
+	pub link: Option<QualFileName>,
+	pub data: Vec<u8>,
+}
+
+impl VirtualFile {
+	/// A file at `DEFAULT_ADDRESS` with no link.
+	pub fn new(name: QualFileName, file_type: FileType, data: Vec<u8>) -> Self {
+		Self {
+			name,
+			file_type,
+			address: DEFAULT_ADDRESS,
+			timestamp: 0,
+			version: ShortVersion::new(1, 0, 0, 0),
+			link: None,
+			data,
+		}
+	}
+	pub fn size(&self) -> FileSize {
+		self.data.len().try_into().expect("Virtual file is too large")
+	}
+	pub fn crc(&self) -> u32 {
+		*0u32.update_crc(&self.data)
+	}
+}
+
+/// Files are kept in insertion order, which determines their index within a category.
+#[derive(Debug, Default)]
+pub struct Filesystem {
+	files: Vec<VirtualFile>,
+}
+
+impl Filesystem {
+	pub fn get(&self, name: &QualFileName) -> Option<&VirtualFile> {
+		self.files.iter().find(|file| &file.name == name)
+	}
+	pub fn in_category(&self, category: Category) -> impl Iterator<Item = &VirtualFile> {
+		self.files.iter().filter(move |file| file.name.category == category)
+	}
+	/// Replaces any existing file with the same name, keeping its index.
+	pub fn insert(&mut self, file: VirtualFile) {
+		match self.files.iter_mut().find(|existing| existing.name == file.name) {
+			Some(existing) => *existing = file,
+			None => self.files.push(file),
+		}
+	}
+	pub fn remove(&mut self, name: &QualFileName) -> Option<VirtualFile> {
+		let idx = self.files.iter().position(|file| &file.name == name)?;
+		Some(self.files.remove(idx))
+	}
+	/// The total size of all files.
+	pub fn used(&self) -> usize {
+		self.files.iter().map(|file| file.data.len()).sum()
+	}
+}
@@ -0,0 +1,87 @@
+//! Framing of commands as seen from the brain's side.
+
+use crate::crc::CrcComputable;
+use crate::device::{CommandId, Device, ResponseByte};
+
+/// A command received from the host.
+#[derive(Debug)]
+pub enum Request {
+	Simple(CommandId),
+	Extended { command: CommandId, payload: Vec<u8>, crc_valid: bool },
+}
+
+/// What to send back in response to an extended command.
+pub enum Reply {
+	/// The usual ACK, followed by a payload.
+	Ack(Vec<u8>),
+	/// A payload without a response byte, as for file transfer reads.
+	Raw(Vec<u8>),
+	Nack(ResponseByte),
+}
+
+/// Take one complete request off the front of `input`, discarding any garbage before the command header.
+/// Returns `None` if more data is needed.
+pub fn take_request(input: &mut Vec<u8>) -> Option<Request> {
+	match input.windows(Device::COMMAND_HEADER.len()).position(|window| window == Device::COMMAND_HEADER) {
+		Some(start) => {
+			input.drain(..start);
+		}
+		None => {
+			// keep what could be the start of a header
+			let keep_from = input.len().saturating_sub(Device::COMMAND_HEADER.len() - 1);
+			input.drain(..keep_from);
+			return None;
+		}
+	}
+	let command = *input.get(Device::COMMAND_HEADER.len())?;
+	if command != Device::EXT_COMMAND {
+		input.drain(..Device::COMMAND_HEADER.len() + 1);
+		return Some(Request::Simple(command));
+	}
+	let ext_command = *input.get(Device::COMMAND_HEADER.len() + 1)?;
+	let length_start = Device::COMMAND_HEADER.len() + 2;
+	let (payload_len, varint_len) = Device::decode_vex_varint(input.get(length_start..)?)?;
+	let payload_start = length_start + varint_len;
+	let frame_len = payload_start + payload_len + std::mem::size_of::<u16>();
+	if input.len() < frame_len {
+		return None;
+	}
+	let frame: Vec<u8> = input.drain(..frame_len).collect();
+	let crc_valid = *0u16.update_crc(&frame) == 0;
+	Some(Request::Extended {
+		command: ext_command,
+		payload: frame[payload_start..payload_start + payload_len].to_vec(),
+		crc_valid,
+	})
+}
+
+/// The response to a simple command: the header, the echoed command, and a length-prefixed payload.
+pub fn encode_simple_response(command: CommandId, payload: &[u8]) -> Vec<u8> {
+	let mut ret = Device::RESPONSE_HEADER.to_vec();
+	ret.push(command);
+	ret.push(payload.len().try_into().expect("Simple response payload is too large"));
+	ret.extend_from_slice(payload);
+	ret
+}
+
+/// The response to an extended command, including the trailing CRC.
+pub fn encode_ext_response(command: CommandId, reply: &Reply) -> Vec<u8> {
+	let (response_byte, payload): (Option<ResponseByte>, &[u8]) = match reply {
+		Reply::Ack(payload) => (Some(ResponseByte::Ack), payload),
+		Reply::Raw(payload) => (None, payload),
+		Reply::Nack(response_byte) => (Some(*response_byte), &[]),
+	};
+	// the length includes the echoed command and the CRC
+	let length = 1 + response_byte.map_or(0, |_| 1) + payload.len() + std::mem::size_of::<u16>();
+	let mut ret = Device::RESPONSE_HEADER.to_vec();
+	ret.push(Device::EXT_COMMAND);
+	ret.extend(Device::encode_vex_varint(length).expect("Extended response payload is too large"));
+	ret.push(command);
+	if let Some(response_byte) = response_byte {
+		ret.push(response_byte as u8);
+	}
+	ret.extend_from_slice(payload);
+	let crc = *0u16.update_crc(&ret);
+	ret.extend_from_slice(&crc.to_be_bytes());
+	ret
+}
@@ -0,0 +1,182 @@
+//! An in-process emulation of a V5 brain, for testing without hardware.
+//!
+//! The emulator speaks the same wire protocol as the real device, and implements `Transport`, so a `Device` can be driven over it as usual:
+//!
+//! ```
+//! use v5_device::emulator::VirtualBrain;
+//!
+//! let brain = VirtualBrain::default();
+//! let mut device = brain.connect().unwrap();
+//! assert_eq!(device.num_files(Default::default()).unwrap(), 0);
+//! ```
+
+use crate::device::filesystem::{Category, PacketSize, QualFileName};
+use crate::device::helpers::{LongVersion, SystemId};
+use crate::device::{CommandId, Device, ResponseByte, Result, UploadableType};
+use crate::transport::Transport;
+use std::io::{self, Read, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+mod filesystem;
+mod frame;
+mod payloads;
+mod state;
+#[cfg(test)]
+mod tests;
+
+pub use filesystem::VirtualFile;
+use state::State;
+
+/// How the emulated device presents itself.
+pub struct Config {
+	pub product: UploadableType,
+	pub version: LongVersion,
+	pub system_id: SystemId,
+	/// The maximum packet size for file transfers.
+	pub max_packet_size: PacketSize,
+	/// The total size of all files, past which uploads fail with `Enospc`.
+	pub capacity: usize,
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Self {
+			product: UploadableType::Brain,
+			version: LongVersion::new(1, 1, 0, 0, 0),
+			system_id: 0x1234_abcd,
+			max_packet_size: 512,
+			capacity: 8 * 1024 * 1024,
+		}
+	}
+}
+
+/// A handle to an emulated brain. Clones share the same brain, so the state can be inspected while a `Device` is using it.
+#[derive(Clone)]
+pub struct VirtualBrain(Arc<Mutex<State>>);
+
+impl Default for VirtualBrain {
+	fn default() -> Self {
+		Self::new(Config::default())
+	}
+}
+
+impl VirtualBrain {
+	pub fn new(config: Config) -> Self {
+		Self(Arc::new(Mutex::new(State::new(config))))
+	}
+	fn state(&self) -> MutexGuard<'_, State> {
+		self.0.lock().expect("Emulator state was poisoned")
+	}
+	/// Create a `Device` that communicates with this brain.
+	pub fn connect(&self) -> Result<Device> {
+		let ty = self.state().config.product;
+		Device::from_transport(ty, Box::new(self.clone()))
+	}
+
+	/// Create an `AsyncDevice` that communicates with this brain.
+	#[cfg(feature = "async")]
+	pub fn connect_async(&self) -> crate::device::asynchronous::AsyncDevice {
+		let ty = self.state().config.product;
+		crate::device::asynchronous::AsyncDevice::from_transport(ty, Box::new(self.clone()))
+	}
+
+	/// Respond to the next instance of the specified extended command with a NACK instead of handling it.
+	/// Multiple injections for the same command are used in order.
+	pub fn inject_nack(&self, command: CommandId, response: ResponseByte) {
+		self.state().injected.push((command, response));
+	}
+	/// Handle the next instance of the specified extended command as usual, but corrupt the CRC of the response, as a flaky connection might.
+	/// Multiple injections for the same command are used in order.
+	pub fn inject_corruption(&self, command: CommandId) {
+		self.state().corrupted.push(command);
+	}
+	pub fn file(&self, name: &QualFileName) -> Option<VirtualFile> {
+		self.state().filesystem.get(name).cloned()
+	}
+	pub fn files(&self, category: Category) -> Vec<VirtualFile> {
+		self.state().filesystem.in_category(category).cloned().collect()
+	}
+	/// Add a file, replacing any existing file with the same name.
+	pub fn insert_file(&self, file: VirtualFile) {
+		self.state().filesystem.insert(file);
+	}
+	/// The file that was last executed, if execution has not been stopped since.
+	pub fn running(&self) -> Option<QualFileName> {
+		self.state().running
+	}
+	/// Set the framebuffer returned by screen captures. It must be `Device::SCREEN_TOTAL_SIZE` bytes of BGRA.
+	pub fn set_screen(&self, screen: Vec<u8>) {
+		assert_eq!(screen.len(), Device::SCREEN_TOTAL_SIZE, "Wrong framebuffer size");
+		self.state().screen = screen;
+	}
+}
+
+/// Reading returns any pending response data. Because responses are produced as soon as a command is written, there is no point in waiting, so an empty buffer times out immediately.
+impl Read for VirtualBrain {
+	fn read(&mut self, output: &mut [u8]) -> io::Result<usize> {
+		match self.state().send(output) {
+			0 if !output.is_empty() => Err(io::Error::new(io::ErrorKind::TimedOut, "Emulated device did not respond")),
+			amount => Ok(amount),
+		}
+	}
+}
+
+impl Write for VirtualBrain {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		self.state().receive(data);
+		Ok(data.len())
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Transport for VirtualBrain {
+	fn name(&self) -> Option<String> {
+		Some(format!("virtual {}", self.state().config.product))
+	}
+	fn timeout(&self) -> Duration {
+		self.state().timeout
+	}
+	fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+		self.state().timeout = timeout;
+		Ok(())
+	}
+}
+
+/// See the `Read` implementation.
+#[cfg(feature = "async")]
+impl tokio::io::AsyncRead for VirtualBrain {
+	fn poll_read(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>, buf: &mut tokio::io::ReadBuf<'_>) -> std::task::Poll<io::Result<()>> {
+		let amount = self.state().send(buf.initialize_unfilled());
+		std::task::Poll::Ready(match amount {
+			0 if buf.remaining() > 0 => Err(io::Error::new(io::ErrorKind::TimedOut, "Emulated device did not respond")),
+			amount => {
+				buf.advance(amount);
+				Ok(())
+			}
+		})
+	}
+}
+
+#[cfg(feature = "async")]
+impl tokio::io::AsyncWrite for VirtualBrain {
+	fn poll_write(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>, data: &[u8]) -> std::task::Poll<io::Result<usize>> {
+		self.state().receive(data);
+		std::task::Poll::Ready(Ok(data.len()))
+	}
+	fn poll_flush(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> std::task::Poll<io::Result<()>> {
+		std::task::Poll::Ready(Ok(()))
+	}
+	fn poll_shutdown(self: std::pin::Pin<&mut Self>, _cx: &mut std::task::Context<'_>) -> std::task::Poll<io::Result<()>> {
+		std::task::Poll::Ready(Ok(()))
+	}
+}
+
+#[cfg(feature = "async")]
+impl crate::transport::asynchronous::AsyncTransport for VirtualBrain {
+	fn name(&self) -> Option<String> {
+		Transport::name(self)
+	}
+}
@@ -0,0 +1,122 @@
+//! Payloads as seen from the brain's side: requests are decoded and responses are encoded.
+//!
+//! These intentionally don't reuse the host-side structures in `device::send` and `device::receive`, so that the emulator checks them rather than agreeing with them by construction.
+
+use crate::device::filesystem::{Address, Category, Channel, FileIndex, FileName, FileSize, FileType, Function, PacketSize, Target};
+use crate::device::helpers::{LongVersion, ShortVersion, SystemId};
+use encde::{Decode, Encode};
+
+#[derive(Encode)]
+pub struct DeviceInfo {
+	pub version: LongVersion,
+	pub product: u8,
+	#[encde(pad_after = 1)]
+	pub flags: u8,
+}
+
+#[derive(Encode)]
+pub struct ExtendedDeviceInfo {
+	#[encde(pad_before = 1)]
+	pub system_version: ShortVersion,
+	pub cpu0_version: ShortVersion,
+	#[encde(pad_after = 3)]
+	pub cpu1_version: ShortVersion,
+	pub touch_version: u8,
+	#[encde(pad_after = 12)]
+	pub system_id: SystemId,
+}
+
+#[derive(Encode)]
+pub struct ExtendedDeviceInfoNew {
+	pub common: ExtendedDeviceInfo,
+	#[encde(pad_after = 3)]
+	pub unknown: u8,
+}
+
+#[derive(Encode)]
+pub struct SystemFlags {
+	pub flags: u32,
+	pub battery_percent: u8,
+	pub controller_battery_percent: u8,
+	pub partner_controller_battery_percent: u8,
+	pub current_program: u8,
+}
+
+#[derive(Decode)]
+pub struct SetChannel {
+	pub _options: u8,
+	pub channel: Channel,
+}
+
+#[derive(Decode)]
+pub struct StartFileTransfer {
+	pub function: Function,
+	pub target: Target,
+	pub category: Category,
+	pub overwrite: bool,
+	pub size: FileSize,
+	pub address: Address,
+	pub crc: u32,
+	pub file_type: FileType,
+	/// Kept raw to avoid time zone conversions.
+	pub timestamp: u32,
+	pub version: ShortVersion,
+	pub name: FileName,
+}
+
+#[derive(Encode)]
+pub struct StartFileTransferReply {
+	pub max_packet_size: PacketSize,
+	pub file_size: FileSize,
+	pub crc: u32,
+}
+
+#[derive(Decode)]
+pub struct FileTransferRead {
+	pub address: Address,
+	pub size: PacketSize,
+}
+
+/// Used by set-link, delete, execute, and get-metadata-by-name.
+#[derive(Decode)]
+pub struct FileWithOptions {
+	pub category: Category,
+	pub options: u8,
+	pub name: FileName,
+}
+
+#[derive(Decode)]
+pub struct CategoryWithOptions {
+	pub category: Category,
+	pub _options: u8,
+}
+
+#[derive(Decode)]
+pub struct IndexWithOptions {
+	pub index: FileIndex,
+	pub _options: u8,
+}
+
+#[derive(Encode)]
+pub struct FileMetadataByIndex {
+	pub idx: FileIndex,
+	pub size: FileSize,
+	pub address: Address,
+	pub crc: u32,
+	pub file_type: FileType,
+	pub timestamp: u32,
+	pub version: ShortVersion,
+	pub name: FileName,
+}
+
+#[derive(Encode)]
+pub struct FileMetadataByName {
+	pub linked_category: Category,
+	pub size: FileSize,
+	pub address: Address,
+	pub crc: u32,
+	pub file_type: FileType,
+	pub timestamp: u32,
+	pub version: ShortVersion,
+	pub linked_name: FileName,
+}
@@ -0,0 +1,353 @@
+//! The emulated brain's state and command handlers.
+
+use super::filesystem::{Filesystem, VirtualFile};
+use super::frame::{self, Reply, Request};
+use super::payloads;
+use super::Config;
+use crate::crc::CrcComputable;
+use crate::device::filesystem::{Address, Category, Channel, FileSize, FileType, Function, QualFileName, Target, TransferCompleteAction};
+use crate::device::helpers::{LongVersion, ShortVersion};
+use crate::device::{CommandId, Device, ResponseByte, UploadableType};
+use encde::util::{decode_from_entire_slice, decode_from_slice, encode_to_vec};
+use encde::{Decode, Encode};
+use log::{debug, trace};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// An in-progress file transfer, started with 0x11 and ended with 0x12.
+struct Transfer {
+	function: Function,
+	file: QualFileName,
+	file_type: FileType,
+	size: FileSize,
+	address: Address,
+	crc: u32,
+	timestamp: u32,
+	version: ShortVersion,
+	link: Option<QualFileName>,
+	/// For uploads, the data received so far. For downloads, the data to be read.
+	data: Vec<u8>,
+}
+
+pub struct State {
+	pub config: Config,
+	pub filesystem: Filesystem,
+	/// Received bytes that do not yet make up a complete command.
+	input: Vec<u8>,
+	/// Response bytes that have not yet been read.
+	output: VecDeque<u8>,
+	pub timeout: Duration,
+	pub channel: Channel,
+	transfer: Option<Transfer>,
+	/// Metadata-by-index has no category, so the category from the last num-files command is used.
+	index_category: Category,
+	/// The next responses to the given commands, instead of handling them.
+	pub injected: Vec<(CommandId, ResponseByte)>,
+	/// Commands whose next responses should have an invalid CRC, after handling them as usual.
+	pub corrupted: Vec<CommandId>,
+	pub running: Option<QualFileName>,
+	pub screen: Vec<u8>,
+}
+
+fn decode<T: Decode>(payload: &[u8]) -> Result<T, ResponseByte> {
+	decode_from_entire_slice(payload).map_err(|_| ResponseByte::GeneralNack)
+}
+
+fn encode(payload: &dyn Encode) -> Reply {
+	Reply::Ack(encode_to_vec(payload).expect("Encoding emulator reply"))
+}
+
+impl State {
+	pub fn new(config: Config) -> Self {
+		Self {
+			config,
+			filesystem: Default::default(),
+			input: Vec::new(),
+			output: VecDeque::new(),
+			timeout: Device::DEFAULT_TIMEOUT,
+			channel: Channel::Pit,
+			transfer: None,
+			index_category: Category::default(),
+			injected: Vec::new(),
+			corrupted: Vec::new(),
+			running: None,
+			screen: vec![0; Device::SCREEN_TOTAL_SIZE],
+		}
+	}
+
+	/// Accept bytes from the host, handling any commands that are now complete.
+	pub fn receive(&mut self, data: &[u8]) {
+		self.input.extend_from_slice(data);
+		while let Some(request) = frame::take_request(&mut self.input) {
+			trace!("emulator received {:?}", request);
+			let response = match request {
+				Request::Simple(command) => match self.handle_simple(command) {
+					Some(payload) => frame::encode_simple_response(command, &payload),
+					// the real device ignores commands it doesn't know
+					None => continue,
+				},
+				Request::Extended { command, payload, crc_valid } => {
+					let reply = if !crc_valid {
+						Reply::Nack(ResponseByte::ReceivedCrcError)
+					} else if let Some(response_byte) = self.take_injected(command) {
+						Reply::Nack(response_byte)
+					} else {
+						self.handle_ext(command, &payload).unwrap_or_else(Reply::Nack)
+					};
+					let mut response = frame::encode_ext_response(command, &reply);
+					if let Some(idx) = self.corrupted.iter().position(|&corrupted| corrupted == command) {
+						self.corrupted.remove(idx);
+						debug!("emulator corrupting response to command {:#02x}", command);
+						*response.last_mut().unwrap() ^= 0xff;
+					}
+					response
+				}
+			};
+			self.output.extend(response);
+		}
+	}
+	/// Give response bytes to the host. Returns 0 if there is nothing to read.
+	pub fn send(&mut self, output: &mut [u8]) -> usize {
+		let amount = std::cmp::min(output.len(), self.output.len());
+		for (dest, src) in output.iter_mut().zip(self.output.drain(..amount)) {
+			*dest = src;
+		}
+		amount
+	}
+
+	fn take_injected(&mut self, command: CommandId) -> Option<ResponseByte> {
+		let idx = self.injected.iter().position(|&(injected_command, _)| injected_command == command)?;
+		let (_, response_byte) = self.injected.remove(idx);
+		debug!("emulator injecting {} for command {:#02x}", response_byte, command);
+		Some(response_byte)
+	}
+
+	fn handle_simple(&mut self, command: CommandId) -> Option<Vec<u8>> {
+		match command {
+			0xa4 => {
+				let (product, flags) = match self.config.product {
+					UploadableType::Brain => (0x10, 0),
+					// connected over the wire
+					UploadableType::Controller => (0x11, 0b01),
+				};
+				Some(encode_to_vec(&payloads::DeviceInfo { version: self.config.version, product, flags }).expect("Encoding emulator reply"))
+			}
+			_ => None,
+		}
+	}
+
+	fn handle_ext(&mut self, command: CommandId, payload: &[u8]) -> Result<Reply, ResponseByte> {
+		match command {
+			0x10 => {
+				let args: payloads::SetChannel = decode(payload)?;
+				self.channel = args.channel;
+				Ok(Reply::Ack(Vec::new()))
+			}
+			0x11 => self.start_file_transfer(decode(payload)?),
+			0x12 => self.end_file_transfer(decode(payload)?),
+			0x13 => self.ft_write(payload),
+			0x14 => self.ft_read(decode(payload)?),
+			0x15 => {
+				let args: payloads::FileWithOptions = decode(payload)?;
+				let transfer = self.transfer.as_mut().ok_or(ResponseByte::UninitializedUploadDownload)?;
+				transfer.link = Some(QualFileName { category: args.category, name: args.name });
+				Ok(Reply::Ack(Vec::new()))
+			}
+			0x16 => {
+				let args: payloads::CategoryWithOptions = decode(payload)?;
+				self.index_category = args.category;
+				let num_files = self.filesystem.in_category(args.category).count() as i16;
+				Ok(encode(&num_files))
+			}
+			0x17 => {
+				let args: payloads::IndexWithOptions = decode(payload)?;
+				let file = self.filesystem.in_category(self.index_category).nth(args.index as usize).ok_or(ResponseByte::Enoent)?;
+				Ok(encode(&payloads::FileMetadataByIndex {
+					idx: args.index,
+					size: file.size(),
+					address: file.address,
+					crc: file.crc(),
+					file_type: file.file_type,
+					timestamp: file.timestamp,
+					version: file.version,
+					name: file.name.name,
+				}))
+			}
+			0x18 => {
+				let args: payloads::FileWithOptions = decode(payload)?;
+				if args.options & 0x80 == 0x80 {
+					self.running = None;
+				} else {
+					let name = QualFileName { category: args.category, name: args.name };
+					self.filesystem.get(&name).ok_or(ResponseByte::Enoent)?;
+					self.running = Some(name);
+				}
+				Ok(Reply::Ack(Vec::new()))
+			}
+			0x19 => {
+				let args: payloads::FileWithOptions = decode(payload)?;
+				let file = self.filesystem.get(&QualFileName { category: args.category, name: args.name }).ok_or(ResponseByte::Enoent)?;
+				let link = file.link.unwrap_or(QualFileName {
+					category: Category::NONE,
+					name: Default::default(),
+				});
+				Ok(encode(&payloads::FileMetadataByName {
+					linked_category: link.category,
+					size: file.size(),
+					address: file.address,
+					crc: file.crc(),
+					file_type: file.file_type,
+					timestamp: file.timestamp,
+					version: file.version,
+					linked_name: link.name,
+				}))
+			}
+			0x1b => {
+				let args: payloads::FileWithOptions = decode(payload)?;
+				let removed = self.filesystem.remove(&QualFileName { category: args.category, name: args.name }).ok_or(ResponseByte::Enoent)?;
+				if let (true, Some(link)) = (args.options & 0x80 == 0x80, removed.link) {
+					self.filesystem.remove(&link);
+				}
+				Ok(Reply::Ack(Vec::new()))
+			}
+			0x20 => Ok(encode(&payloads::SystemFlags {
+				flags: 0,
+				battery_percent: 100,
+				controller_battery_percent: 100,
+				partner_controller_battery_percent: 0,
+				current_program: self.running_slot(),
+			})),
+			0x22 => {
+				let common = payloads::ExtendedDeviceInfo {
+					system_version: self.config.version.into(),
+					cpu0_version: self.config.version.into(),
+					cpu1_version: self.config.version.into(),
+					touch_version: 0,
+					system_id: self.config.system_id,
+				};
+				if self.config.product == UploadableType::Brain && self.config.version >= LongVersion::new(1, 0, 13, 0, 0) {
+					Ok(encode(&payloads::ExtendedDeviceInfoNew { common, unknown: 0 }))
+				} else {
+					Ok(encode(&common))
+				}
+			}
+			0x28 => Ok(Reply::Ack(Vec::new())),
+			_ => Err(ResponseByte::GeneralNack),
+		}
+	}
+
+	/// The slot of the running program, taken from its name if it's a slot's binary like `slot_1.bin`.
+	fn running_slot(&self) -> u8 {
+		let name = match self.running {
+			Some(ref running) => running.name,
+			None => return 0,
+		};
+		name.as_str().ok().and_then(|name| name.strip_prefix("slot_")?.strip_suffix(".bin")?.parse().ok()).unwrap_or(1)
+	}
+
+	fn start_file_transfer(&mut self, args: payloads::StartFileTransfer) -> Result<Reply, ResponseByte> {
+		let file = QualFileName { category: args.category, name: args.name };
+		let (size, crc, data) = match (args.function, args.target) {
+			(Function::Download, Target::Screen) => {
+				let data = self.screen.clone();
+				(data.len() as FileSize, *0u32.update_crc(&data), data)
+			}
+			(Function::Download, _) => {
+				let existing = self.filesystem.get(&file).ok_or(ResponseByte::Enoent)?;
+				(existing.size(), existing.crc(), existing.data.clone())
+			}
+			(Function::Upload, _) => {
+				let replaced_size = match self.filesystem.get(&file) {
+					Some(_) if !args.overwrite => return Err(ResponseByte::Eexist),
+					Some(existing) => existing.data.len(),
+					None => 0,
+				};
+				if self.filesystem.used() - replaced_size + args.size as usize > self.config.capacity {
+					return Err(ResponseByte::Enospc);
+				}
+				(args.size, args.crc, Vec::with_capacity(args.size as usize))
+			}
+		};
+		self.transfer = Some(Transfer {
+			function: args.function,
+			file,
+			file_type: args.file_type,
+			size,
+			address: args.address,
+			crc,
+			timestamp: args.timestamp,
+			version: args.version,
+			link: None,
+			data,
+		});
+		Ok(encode(&payloads::StartFileTransferReply {
+			max_packet_size: self.config.max_packet_size,
+			file_size: size,
+			crc,
+		}))
+	}
+
+	fn ft_write(&mut self, payload: &[u8]) -> Result<Reply, ResponseByte> {
+		let (address, data_len): (Address, usize) = decode_from_slice(payload).map_err(|_| ResponseByte::PayloadTooSmall)?;
+		let data = &payload[payload.len() - data_len..];
+		let transfer = match self.transfer {
+			Some(ref mut transfer) if transfer.function == Function::Upload => transfer,
+			_ => return Err(ResponseByte::UninitializedUploadDownload),
+		};
+		if data.len() & 0b11 != 0 {
+			return Err(ResponseByte::DataNotAligned);
+		}
+		// rewriting earlier packets is allowed, which happens when the host retries
+		let offset = address.wrapping_sub(transfer.address) as usize;
+		if offset > transfer.data.len() {
+			return Err(ResponseByte::PacketAddressWrong);
+		}
+		transfer.data.truncate(offset);
+		transfer.data.extend_from_slice(data);
+		Ok(Reply::Ack(Vec::new()))
+	}
+
+	fn ft_read(&mut self, args: payloads::FileTransferRead) -> Result<Reply, ResponseByte> {
+		let transfer = match self.transfer {
+			Some(ref transfer) if transfer.function == Function::Download => transfer,
+			_ => return Err(ResponseByte::UninitializedUploadDownload),
+		};
+		if args.size > self.config.max_packet_size {
+			return Err(ResponseByte::RequestedTransferTooLarge);
+		}
+		let offset = args.address.wrapping_sub(transfer.address) as usize;
+		let mut reply = encode_to_vec(&args.address).expect("Encoding emulator reply");
+		// reading past the end gives zeroes
+		reply.extend((offset..offset + args.size as usize).map(|idx| transfer.data.get(idx).copied().unwrap_or(0)));
+		Ok(Reply::Raw(reply))
+	}
+
+	fn end_file_transfer(&mut self, action: TransferCompleteAction) -> Result<Reply, ResponseByte> {
+		// the host sends this after deleting files too, where there is no transfer to end
+		if let Some(mut transfer) = self.transfer.take() {
+			if transfer.function == Function::Upload {
+				if transfer.data.len() < transfer.size as usize {
+					return Err(ResponseByte::DownloadedLengthWrong);
+				}
+				// drop the padding of the last packet
+				transfer.data.truncate(transfer.size as usize);
+				if *0u32.update_crc(&transfer.data) != transfer.crc {
+					return Err(ResponseByte::ProgramCrcError);
+				}
+				self.filesystem.insert(VirtualFile {
+					name: transfer.file,
+					file_type: transfer.file_type,
+					address: transfer.address,
+					timestamp: transfer.timestamp,
+					version: transfer.version,
+					link: transfer.link,
+					data: transfer.data,
+				});
+				if action != TransferCompleteAction::NoRun {
+					self.running = Some(transfer.file);
+				}
+			}
+		}
+		Ok(Reply::Ack(Vec::new()))
+	}
+}
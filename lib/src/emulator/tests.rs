@@ -0,0 +1,228 @@
+use super::{VirtualBrain, VirtualFile};
+use crate::device::filesystem::{self as fs, QualFile, QualFileName};
+use crate::device::screen::{Framebuffer, Rect};
+use crate::device::{send, DeviceError, ProtocolError, ResponseByte};
+use crate::program::{self, SlotNumber};
+use encde::util::VecWriter;
+use std::str::FromStr;
+
+fn qual_file(s: &str) -> QualFile {
+	QualFile::from_str(s).unwrap()
+}
+
+#[test]
+fn device_info() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	assert!(matches!(device.device_info().unwrap().product, crate::device::helpers::Product::Brain(_)));
+	assert_eq!(device.extended_device_info().unwrap().system_id, 0x1234_abcd);
+}
+
+#[test]
+fn write_then_read() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let file = qual_file("reveng:test.bin");
+	// several packets, and not a multiple of 4
+	let data: Vec<u8> = (0..2001u32).map(|x| x as u8).collect();
+	device.write_file_from_slice(&data, &file, &Default::default()).unwrap();
+	assert_eq!(brain.file(&file.common).unwrap().data, data);
+
+	let mut output = VecWriter::new();
+	device.read_file_to_stream(&mut output, &file, &Default::default()).unwrap();
+	assert_eq!(output.into_inner(), data);
+
+	let metadata = device.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common)).unwrap().unwrap();
+	assert_eq!(metadata.address, fs::DEFAULT_ADDRESS);
+	assert_eq!(metadata.size, 2001);
+	assert_eq!(device.list_all_files(fs::Category::REVENG).unwrap().len(), 1);
+}
+
+#[test]
+fn write_compressed() {
+	use std::io::Read;
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let file = qual_file("reveng:test.bin");
+	let data: Vec<u8> = (0..5000u32).map(|x| (x % 10) as u8).collect();
+	let args = fs::WriteArgs { compress: true, ..Default::default() };
+	device.write_file_from_stream(&mut data.as_slice(), &file, data.len() as u32, 0, &args).unwrap();
+	let compressed = brain.file(&file.common).unwrap().data;
+	assert!(compressed.len() < data.len());
+
+	let mut output = VecWriter::new();
+	device.read_file_to_stream(&mut output, &file, &Default::default()).unwrap();
+	let mut decompressed = Vec::new();
+	flate2::read::GzDecoder::new(output.into_inner().as_slice()).read_to_end(&mut decompressed).unwrap();
+	assert_eq!(decompressed, data);
+
+	let metadata = device.get_file_metadata_by_name(&send::FileMetadataByName::new(&file.common)).unwrap().unwrap();
+	assert_eq!(metadata.size as usize, compressed.len());
+}
+
+#[test]
+fn existing_file_needs_overwrite() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let file = qual_file("user:a.txt");
+	brain.insert_file(VirtualFile::new(file.common, file.ty, b"old".to_vec()));
+	let ret = device.write_file_from_slice(b"new", &file, &Default::default());
+	assert!(matches!(ret, Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Eexist)))));
+	device.write_file_from_slice(b"new", &file, &fs::WriteArgs { overwrite: true, ..Default::default() }).unwrap();
+	assert_eq!(brain.file(&file.common).unwrap().data, b"new");
+}
+
+#[test]
+fn injected_nack() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	brain.inject_nack(0x11, ResponseByte::Enospc);
+	let ret = device.write_file_from_slice(b"data", &qual_file("a.bin"), &Default::default());
+	assert!(matches!(ret, Err(DeviceError::Protocol(ProtocolError::Nack(ResponseByte::Enospc)))));
+	assert!(brain.files(fs::Category::USER).is_empty());
+}
+
+#[test]
+fn programs() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let ini = "[program]\nversion = 1\nname = test\nslot = 2\nicon = USER902x.bmp\ndescription = \ndate = 2022-01-01\n";
+	let bin_name = QualFileName::from_str("slot_3.bin").unwrap();
+	brain.insert_file(VirtualFile::new(QualFileName::from_str("slot_3.ini").unwrap(), fs::FileType::try_from(&b"ini"[..]).unwrap(), ini.as_bytes().to_vec()));
+	brain.insert_file(VirtualFile::new(bin_name, fs::FileType::try_from(&b"bin"[..]).unwrap(), vec![0; 8]));
+
+	let slot = SlotNumber::try_from(3).unwrap();
+	let programs = program::get_all(&mut device).unwrap();
+	assert_eq!(programs.iter().flatten().count(), 1);
+	assert_eq!(programs[slot.to_index()].as_ref().unwrap().name, "test");
+
+	program::run(&mut device, slot).unwrap();
+	assert_eq!(brain.running(), Some(bin_name));
+	assert_eq!(device.system_flags().unwrap().current_program, 3);
+	device.stop_execution().unwrap();
+	assert_eq!(brain.running(), None);
+	assert!(!device.program_running().unwrap());
+
+	assert!(program::remove(&mut device, slot, false).unwrap());
+	assert!(brain.files(fs::Category::USER).is_empty());
+}
+
+#[test]
+fn upload_programs() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let ini = |name: &str, version: &str, slot| program::ProgramIni {
+		version: version.to_owned(),
+		name: name.to_owned(),
+		slot,
+		icon: "USER902x.bmp".to_owned(),
+		description: "A test".to_owned(),
+		date: "2022-01-01T00:00:00".to_owned(),
+	};
+	let programs = program::get_all(&mut device).unwrap();
+	let slot = program::choose_slot(&programs, "a", "1.0.0").unwrap();
+	assert_eq!(slot, SlotNumber::try_from(1).unwrap());
+	let run = program::UploadArgs {
+		action: fs::TransferCompleteAction::RunImmediately,
+		..Default::default()
+	};
+	assert!(!program::upload::is_uploaded(&mut device, slot, b"binary", &run).unwrap());
+	program::upload(&mut device, &ini("a", "1.0.0", slot), b"binary", &run).unwrap();
+	assert!(program::upload::is_uploaded(&mut device, slot, b"binary", &run).unwrap());
+	assert_eq!(brain.running(), Some(QualFileName::from_str("slot_1.bin").unwrap()));
+	assert_eq!(brain.file(&QualFileName::from_str("slot_1.bin").unwrap()).unwrap().data, b"binary");
+
+	let programs = program::get_all(&mut device).unwrap();
+	assert_eq!(programs[0].as_ref().unwrap().description, "A test");
+	assert_eq!(program::choose_slot(&programs, "a", "1.0.1").unwrap(), slot);
+	assert_eq!(program::choose_slot(&programs, "b", "1.0.0").unwrap(), SlotNumber::try_from(2).unwrap());
+	assert!(matches!(program::choose_slot(&programs, "a", "0.9.0"), Err(program::upload::ChooseSlotError::NewerVersion { .. })));
+
+	let slot = SlotNumber::try_from(2).unwrap();
+	let cold_name = program::upload::cold_file(b"cold").common;
+	assert!(program::upload_hot_cold(&mut device, &ini("b", "1.0.0", slot), b"hot 1", b"cold", &Default::default()).unwrap());
+	assert!(!program::upload_hot_cold(&mut device, &ini("b", "1.0.1", slot), b"hot 2", b"cold", &Default::default()).unwrap());
+	let hot = brain.file(&QualFileName::from_str("slot_2.bin").unwrap()).unwrap();
+	assert_eq!((hot.data.as_slice(), hot.link), (&b"hot 2"[..], Some(cold_name)));
+	assert_eq!(brain.file(&cold_name).unwrap().address, program::upload::COLD_ADDRESS);
+	let new_cold_name = program::upload::cold_file(b"new cold").common;
+	assert!(program::upload_hot_cold(&mut device, &ini("b", "1.0.2", slot), b"hot 3", b"new cold", &Default::default()).unwrap());
+	assert_eq!(brain.file(&new_cold_name).unwrap().data, b"new cold");
+	// another slot with the same cold package shares it
+	let other_slot = SlotNumber::try_from(3).unwrap();
+	assert!(!program::upload_hot_cold(&mut device, &ini("c", "1.0.0", other_slot), b"hot 4", b"new cold", &Default::default()).unwrap());
+	assert_eq!(brain.file(&QualFileName::from_str("slot_3.bin").unwrap()).unwrap().link, Some(new_cold_name));
+}
+
+#[test]
+fn screen_capture() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let screen: Vec<u8> = (0..crate::device::Device::SCREEN_TOTAL_SIZE).map(|x| x as u8).collect();
+	brain.set_screen(screen.clone());
+	let mut output = VecWriter::new();
+	device.capture_screen(&mut output).unwrap();
+	assert_eq!(output.into_inner(), screen);
+
+	let image = device.capture_screen_image().unwrap();
+	assert_eq!(image.bgra(), screen);
+	let (x, y) = (3, 1);
+	let start = (y * crate::device::Device::SCREEN_WIDTH + x) * 4;
+	let pixel = image.pixel(x, y);
+	assert_eq!([pixel.blue, pixel.green, pixel.red, pixel.alpha], screen[start..start + 4]);
+	let rect = Rect::from_str("3,1,2,2").unwrap();
+	let rgb = image.rgb_region(rect);
+	assert_eq!(rgb.len(), 12);
+	assert_eq!(rgb[..3], [screen[start + 2], screen[start + 1], screen[start]]);
+	assert_eq!(image.bgra_region(Framebuffer::FULL), screen);
+}
+
+#[test]
+fn raw_commands() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let response = device.raw_simple_command(0xa4, &[]).unwrap();
+	assert_eq!((response.response_byte, response.payload.len(), response.crc_valid), (None, 8, None));
+	let response = device.raw_ext_command(0x22, &[]).unwrap();
+	assert_eq!(response.known_response_byte(), Some(ResponseByte::Ack));
+	assert_eq!(response.crc_valid, Some(true));
+	// unknown commands are NACKed, which is not an error here
+	let response = device.raw_ext_command(0x7e, &[1, 2, 3]).unwrap();
+	assert_eq!(response.known_response_byte(), Some(ResponseByte::GeneralNack));
+	assert!(response.payload.is_empty());
+}
+
+#[test]
+fn probe() {
+	use crate::device::probe::Outcome;
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	assert_eq!(device.probe_ext_command(0x22, &[]).unwrap(), Outcome::Ack);
+	assert_eq!(device.probe_ext_command(0x7e, &[]).unwrap(), Outcome::Nack(ResponseByte::GeneralNack));
+	// nothing left over to drain, and the link still works afterwards
+	assert_eq!(device.drain_input().unwrap(), 0);
+	assert_eq!(device.probe_ext_command(0x22, &[]).unwrap(), Outcome::Ack);
+}
+
+#[test]
+fn retries_corrupted_responses() {
+	let brain = VirtualBrain::default();
+	let mut device = brain.connect().unwrap();
+	let file = qual_file("user:retry.bin");
+	let data: Vec<u8> = (0..2000u32).map(|x| (x * 7) as u8).collect();
+	// the device accepts the packet, but its ACK is garbled, so the packet is resent to the same address
+	brain.inject_corruption(0x13);
+	device.write_file_from_slice(&data, &file, &Default::default()).unwrap();
+	assert_eq!(brain.file(&file.common).unwrap().data, data);
+
+	brain.inject_corruption(0x14);
+	brain.inject_corruption(0x19);
+	let mut output = VecWriter::new();
+	device.read_file_to_stream(&mut output, &file, &Default::default()).unwrap();
+	assert_eq!(output.into_inner(), data);
+
+	device.set_retry_policy(crate::device::RetryPolicy::NEVER);
+	brain.inject_corruption(0x16);
+	let ret = device.num_files(fs::Category::USER);
+	assert!(matches!(ret, Err(DeviceError::Protocol(ProtocolError::InvalidCrc))));
+}
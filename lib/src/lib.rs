@@ -1,6 +1,10 @@
 pub mod crc;
 pub mod device;
+pub mod emulator;
 pub mod program;
+#[cfg(test)]
+mod test_util;
+pub mod transport;
 pub mod util;
 
 pub use device::Device;
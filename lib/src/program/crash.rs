@@ -0,0 +1,201 @@
+//! Making sense of the crash reports that the PROS kernel prints when user code faults.
+//!
+//! A report looks something like this, with addresses in hex, with or without `0x`:
+//!
+//! ```text
+//! DATA ABORT EXCEPTION
+//!
+//! PC: 7802ba8
+//! LR: 7802b4c
+//! ...
+//! BEGIN STACK TRACE
+//! 	7802ba8
+//! 	7801f10
+//! END OF TRACE
+//! ```
+
+use super::elf::ElfError;
+use crate::device::filesystem::Address;
+use std::fmt::{self, Display, Formatter};
+use std::path::Path;
+
+/// A code address from a crash report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CrashAddress {
+	pub address: Address,
+	/// Whether the address is where execution would have returned to, i.e. just after the call, rather than the faulting instruction.
+	pub is_return_address: bool,
+}
+
+/// Finds the addresses in a crash report, a line at a time.
+#[derive(Debug, Default)]
+pub struct Scanner {
+	in_report: bool,
+	in_trace: bool,
+}
+
+impl Scanner {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// Whether the lines so far have started a crash report that hasn't ended yet.
+	/// A report starts with a line like `DATA ABORT EXCEPTION` and ends with `END OF TRACE`.
+	pub fn in_report(&self) -> bool {
+		self.in_report
+	}
+	/// The address on this line, if it is part of a crash report and has one.
+	pub fn scan(&mut self, line: &str) -> Option<CrashAddress> {
+		let line = line.trim();
+		if line.ends_with("EXCEPTION") {
+			self.in_report = true;
+			self.in_trace = false;
+		} else if line == "BEGIN STACK TRACE" {
+			self.in_trace = true;
+		} else if line == "END OF TRACE" {
+			self.in_report = false;
+			self.in_trace = false;
+		} else if self.in_trace {
+			return parse_address(line).map(|address| CrashAddress { address, is_return_address: true });
+		} else if self.in_report {
+			if let Some(value) = line.strip_prefix("PC:") {
+				return parse_address(value).map(|address| CrashAddress { address, is_return_address: false });
+			}
+			if let Some(value) = line.strip_prefix("LR:") {
+				return parse_address(value).map(|address| CrashAddress { address, is_return_address: true });
+			}
+		}
+		None
+	}
+}
+
+fn parse_address(s: &str) -> Option<Address> {
+	let s = s.trim();
+	let s = s.strip_prefix("0x").unwrap_or(s);
+	Address::from_str_radix(s, 16).ok()
+}
+
+/// Where in the source an address is. There is one of these for each function that was inlined at the address, innermost first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SourceFrame {
+	pub function: Option<String>,
+	pub file: Option<String>,
+	pub line: Option<u32>,
+}
+
+impl Display for SourceFrame {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		formatter.write_str(self.function.as_deref().unwrap_or("??"))?;
+		if let Some(ref file) = self.file {
+			write!(formatter, " at {}", file)?;
+			if let Some(line) = self.line {
+				write!(formatter, ":{}", line)?;
+			}
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug)]
+pub enum SymbolizerError {
+	Io(std::io::Error),
+	Parse(String),
+	/// The ELF has nothing to load, so there's no way to tell where it is in memory.
+	NoSegments,
+}
+
+impl Display for SymbolizerError {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Io(err) => write!(formatter, "reading the ELF: {}", err),
+			Self::Parse(err) => write!(formatter, "parsing the ELF: {}", err),
+			Self::NoSegments => formatter.write_str("the ELF has no loadable segments"),
+		}
+	}
+}
+
+impl std::error::Error for SymbolizerError {}
+
+/// Looks up addresses from the brain in the debugging information of the ELF that the program was built from.
+pub struct Symbolizer {
+	loader: addr2line::Loader,
+	/// What to subtract from an address on the brain to get the corresponding address in the ELF.
+	offset: i64,
+}
+
+impl Symbolizer {
+	/// `load_address` is where the program was uploaded to, e.g. `filesystem::DEFAULT_ADDRESS`.
+	/// The start of the ELF's image is assumed to be there, as `ElfImage` lays it out.
+	pub fn new(elf: &Path, load_address: Address) -> Result<Self, SymbolizerError> {
+		let data = std::fs::read(elf).map_err(SymbolizerError::Io)?;
+		let link_address = match super::elf::load_address(&data) {
+			Ok(address) => address,
+			Err(ElfError::NoLoadableSegments) => return Err(SymbolizerError::NoSegments),
+			Err(err) => return Err(SymbolizerError::Parse(err.to_string())),
+		};
+		let loader = addr2line::Loader::new(elf).map_err(|err| SymbolizerError::Parse(err.to_string()))?;
+		Ok(Self {
+			loader,
+			offset: load_address as i64 - link_address as i64,
+		})
+	}
+	/// Empty if nothing is known about the address.
+	pub fn symbolize(&self, address: CrashAddress) -> Vec<SourceFrame> {
+		let mut probe = (address.address as i64 - self.offset) as u64;
+		// look up the call instruction rather than whatever follows it
+		if address.is_return_address {
+			probe = probe.saturating_sub(1);
+		}
+		let mut ret = Vec::new();
+		if let Ok(mut frames) = self.loader.find_frames(probe) {
+			while let Ok(Some(frame)) = frames.next() {
+				ret.push(SourceFrame {
+					function: frame.function.and_then(|function| function.demangle().ok().map(|name| name.into_owned())),
+					file: frame.location.as_ref().and_then(|location| location.file.map(str::to_owned)),
+					line: frame.location.as_ref().and_then(|location| location.line),
+				});
+			}
+		}
+		if ret.is_empty() {
+			if let Some(symbol) = self.loader.find_symbol(probe) {
+				ret.push(SourceFrame {
+					function: Some(addr2line::demangle_auto(symbol.into(), None).into_owned()),
+					file: None,
+					line: None,
+				});
+			}
+		}
+		ret
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn scan_report() {
+		let report = "hello\nPC: 1234\nDATA ABORT EXCEPTION\n\nPC: 7802ba8\nLR: 0x7802b4c\nr0: 00000001\nBEGIN STACK TRACE\n\t7802ba8\n\t7801f10\nEND OF TRACE\n\t7801f10";
+		let mut scanner = Scanner::new();
+		let found = report.lines().filter_map(|line| scanner.scan(line)).collect::<Vec<_>>();
+		let address = |address, is_return_address| CrashAddress { address, is_return_address };
+		assert_eq!(found, [address(0x7802ba8, false), address(0x7802b4c, true), address(0x7802ba8, true), address(0x7801f10, true)]);
+	}
+
+	#[test]
+	fn symbolize() {
+		// a segment that is only in memory, such as .bss, isn't part of the image even if it's lower
+		let text = [0u8; 0x40];
+		let elf = crate::program::elf::tests::build_elf(&[(0x0780_0000, &text), (0x0700_0000, &[])], &[("first", 0x0780_0000, 0x20), ("second", 0x0780_0020, 0x20)]);
+		let path = std::env::temp_dir().join(format!("v5_symbolize_test_{}.elf", std::process::id()));
+		std::fs::write(&path, &elf).unwrap();
+		let symbolizer = Symbolizer::new(&path, 0x0380_0000);
+		std::fs::remove_file(&path).unwrap();
+		let symbolizer = symbolizer.unwrap();
+		let function = |address, is_return_address| symbolizer.symbolize(CrashAddress { address, is_return_address }).first().and_then(|frame| frame.function.clone());
+		assert_eq!(function(0x0380_0010, false).as_deref(), Some("first"));
+		assert_eq!(function(0x0380_0020, false).as_deref(), Some("second"));
+		// a return address just past the end of `first` is from a call in `first`
+		assert_eq!(function(0x0380_0020, true).as_deref(), Some("first"));
+		assert_eq!(function(0x037f_fff0, false), None);
+	}
+}
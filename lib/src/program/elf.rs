@@ -0,0 +1,211 @@
+//! Turning an ELF into the flat image that the brain loads, as `objcopy -O binary` would.
+
+use crate::device::filesystem::Address;
+use object::elf::{PT_LOAD, SHF_ALLOC};
+use object::read::elf::{ElfFile32, ProgramHeader};
+use object::{Object, ObjectSection, SectionFlags};
+use std::fmt::{self, Display, Formatter};
+
+/// If the segments are spread out further than this, the ELF is probably meant to be uploaded as more than one file, e.g. as a hot binary and a cold package.
+const MAX_IMAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Whether the data looks like an ELF rather than a flat image.
+pub fn is_elf(data: &[u8]) -> bool {
+	data.starts_with(b"\x7fELF")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ElfError {
+	/// Not a 32-bit ELF, or malformed.
+	Parse(String),
+	NoLoadableSegments,
+	TooLarge {
+		size: u64,
+	},
+	/// The ELF was linked to run at a different address than it would be placed at.
+	AddressMismatch {
+		linked: Address,
+		expected: Address,
+	},
+}
+
+impl Display for ElfError {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Parse(err) => write!(formatter, "invalid ELF: {}", err),
+			Self::NoLoadableSegments => formatter.write_str("the ELF has nothing to load"),
+			Self::TooLarge { size } => write!(formatter, "the loadable segments span {} bytes, which is too large for one file", size),
+			Self::AddressMismatch { linked, expected } => write!(formatter, "the ELF is linked for address {:#010x} but would be placed at {:#010x}", linked, expected),
+		}
+	}
+}
+
+impl std::error::Error for ElfError {}
+
+/// An allocated section, for reporting how much space each part of the program takes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+	pub name: String,
+	pub address: Address,
+	pub size: u32,
+}
+
+/// The loadable segments of an ELF, laid out from the lowest load address with any gaps zeroed.
+#[derive(Debug, Clone)]
+pub struct ElfImage {
+	/// The load address of the start of the image.
+	pub address: Address,
+	pub data: Vec<u8>,
+	pub sections: Vec<Section>,
+}
+
+/// The segments that have something to load, by load address, lowest first.
+fn loadable_segments<'a>(file: &ElfFile32<'a, object::Endianness>, elf: &'a [u8]) -> Result<Vec<(Address, &'a [u8])>, ElfError> {
+	let endian = file.endian();
+	let mut segments = file
+		.elf_program_headers()
+		.iter()
+		.filter(|header| header.p_type(endian) == PT_LOAD && header.p_filesz(endian) > 0)
+		.map(|header| Ok((header.p_paddr(endian), header.data(endian, elf).map_err(|_| ElfError::Parse("segment data out of bounds".to_owned()))?)))
+		.collect::<Result<Vec<_>, ElfError>>()?;
+	segments.sort_by_key(|(address, _)| *address);
+	Ok(segments)
+}
+
+/// The lowest load address of the ELF, which is where the start of its image goes. See `ElfImage::address`.
+pub fn load_address(elf: &[u8]) -> Result<Address, ElfError> {
+	let file = ElfFile32::<object::Endianness>::parse(elf).map_err(|err| ElfError::Parse(err.to_string()))?;
+	Ok(loadable_segments(&file, elf)?.first().ok_or(ElfError::NoLoadableSegments)?.0)
+}
+
+impl ElfImage {
+	pub fn parse(elf: &[u8]) -> Result<Self, ElfError> {
+		let file = ElfFile32::<object::Endianness>::parse(elf).map_err(|err| ElfError::Parse(err.to_string()))?;
+		let segments = loadable_segments(&file, elf)?;
+		let address = segments.first().ok_or(ElfError::NoLoadableSegments)?.0;
+		let end = segments.iter().map(|(address, data)| *address as u64 + data.len() as u64).max().unwrap();
+		let size = end - address as u64;
+		if size > MAX_IMAGE_SIZE {
+			return Err(ElfError::TooLarge { size });
+		}
+		let mut data = vec![0u8; size as usize];
+		for (segment_address, segment_data) in segments {
+			let offset = (segment_address - address) as usize;
+			data[offset..offset + segment_data.len()].copy_from_slice(segment_data);
+		}
+		let sections = file
+			.sections()
+			.filter(|section| matches!(section.flags(), SectionFlags::Elf { sh_flags } if sh_flags & SHF_ALLOC as u64 != 0) && section.size() > 0)
+			.map(|section| Section {
+				name: section.name().unwrap_or("?").to_owned(),
+				address: section.address() as Address,
+				size: section.size() as u32,
+			})
+			.collect();
+		Ok(Self { address, data, sections })
+	}
+	/// Refuse to place the image anywhere other than where it was linked for, since it would crash when run.
+	pub fn check_address(&self, expected: Address) -> Result<(), ElfError> {
+		if self.address == expected {
+			Ok(())
+		} else {
+			Err(ElfError::AddressMismatch { linked: self.address, expected })
+		}
+	}
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	/// A little-endian ARM executable with the given loadable segments. A segment without data is only in memory, like .bss, and takes up 0x100 bytes there.
+	/// If there are symbols, which are functions given by name, address, and size, they are put in a symbol table, with the first segment as their section.
+	pub fn build_elf(segments: &[(Address, &[u8])], symbols: &[(&str, Address, u32)]) -> Vec<u8> {
+		const HEADER_SIZE: usize = 52;
+		const PROGRAM_HEADER_SIZE: usize = 32;
+		const SECTION_HEADER_SIZE: usize = 40;
+		const SYMBOL_SIZE: usize = 16;
+		let mut elf = Vec::new();
+		elf.extend(b"\x7fELF\x01\x01\x01\0\0\0\0\0\0\0\0\0");
+		let half = |elf: &mut Vec<u8>, x: u16| elf.extend(x.to_le_bytes());
+		let word = |elf: &mut Vec<u8>, x: u32| elf.extend(x.to_le_bytes());
+		half(&mut elf, 2); // executable
+		half(&mut elf, 40); // ARM
+		word(&mut elf, 1);
+		word(&mut elf, segments[0].0); // entry
+		word(&mut elf, HEADER_SIZE as u32); // program headers
+		let section_headers_offset = elf.len();
+		word(&mut elf, 0); // section headers, filled in later
+		word(&mut elf, 0);
+		half(&mut elf, HEADER_SIZE as u16);
+		half(&mut elf, PROGRAM_HEADER_SIZE as u16);
+		half(&mut elf, segments.len() as u16);
+		half(&mut elf, SECTION_HEADER_SIZE as u16);
+		half(&mut elf, if symbols.is_empty() { 0 } else { 5 });
+		half(&mut elf, if symbols.is_empty() { 0 } else { 4 }); // section names
+		let first_segment_offset = HEADER_SIZE + PROGRAM_HEADER_SIZE * segments.len();
+		let mut offset = first_segment_offset;
+		for (address, data) in segments {
+			let memory_size = if data.is_empty() { 0x100 } else { data.len() as u32 };
+			for value in [PT_LOAD, offset as u32, *address, *address, data.len() as u32, memory_size, 0b111, 4] {
+				word(&mut elf, value);
+			}
+			offset += data.len();
+		}
+		for (_, data) in segments {
+			elf.extend(*data);
+		}
+		if symbols.is_empty() {
+			return elf;
+		}
+
+		let symtab_offset = elf.len();
+		elf.extend([0; SYMBOL_SIZE]);
+		let mut strtab = vec![0u8];
+		for (name, address, size) in symbols {
+			word(&mut elf, strtab.len() as u32);
+			word(&mut elf, *address);
+			word(&mut elf, *size);
+			elf.push(0x12); // global function
+			elf.push(0);
+			half(&mut elf, 1); // .text
+			strtab.extend(name.as_bytes());
+			strtab.push(0);
+		}
+		let strtab_offset = elf.len();
+		elf.extend(&strtab);
+		let shstrtab = b"\0.text\0.symtab\0.strtab\0.shstrtab\0";
+		let shstrtab_offset = elf.len();
+		elf.extend(shstrtab);
+		let section_headers = elf.len() as u32;
+		elf[section_headers_offset..section_headers_offset + 4].copy_from_slice(&section_headers.to_le_bytes());
+		let text = (segments[0].0, first_segment_offset, segments[0].1.len());
+		// name, type, flags, address, offset, size, link, info, alignment, entry size
+		let headers: [[u32; 10]; 5] = [
+			[0; 10],
+			[1, 1, 0b110, text.0, text.1 as u32, text.2 as u32, 0, 0, 4, 0],
+			[7, 2, 0, 0, symtab_offset as u32, ((symbols.len() + 1) * SYMBOL_SIZE) as u32, 3, 1, 4, SYMBOL_SIZE as u32],
+			[15, 3, 0, 0, strtab_offset as u32, strtab.len() as u32, 0, 0, 1, 0],
+			[23, 3, 0, 0, shstrtab_offset as u32, shstrtab.len() as u32, 0, 0, 1, 0],
+		];
+		for header in headers {
+			for value in header {
+				word(&mut elf, value);
+			}
+		}
+		elf
+	}
+
+	#[test]
+	fn flatten_segments() {
+		let elf = build_elf(&[(0x0780_0008, b"data"), (0x0780_0000, b"text")], &[]);
+		assert!(is_elf(&elf));
+		let image = ElfImage::parse(&elf).unwrap();
+		assert_eq!(image.address, 0x0780_0000);
+		assert_eq!(image.data, b"text\0\0\0\0data");
+		assert_eq!(image.check_address(0x0780_0000), Ok(()));
+		assert_eq!(image.check_address(0x0380_0000), Err(ElfError::AddressMismatch { linked: 0x0780_0000, expected: 0x0380_0000 }));
+		let hot_cold = build_elf(&[(0x0380_0000, b"cold"), (0x0780_0000, b"hot")], &[]);
+		assert!(matches!(ElfImage::parse(&hot_cold), Err(ElfError::TooLarge { .. })));
+	}
+}
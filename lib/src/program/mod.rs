@@ -4,9 +4,16 @@ use encde::util::VecWriter;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
+pub mod crash;
+pub mod elf;
 pub mod slot_number;
+pub mod stream;
+pub mod telemetry;
+pub mod test_protocol;
+pub mod upload;
 
 pub use slot_number::SlotNumber;
+pub use upload::{choose_slot, upload, upload_hot_cold, UploadArgs};
 
 const NUM_SLOTS: usize = 8;
 
@@ -17,7 +24,7 @@ pub struct ProgramIniTopLevel {
 
 /// This is intentionally missing fields, or uses String where a more specific type could be used.
 /// We want to keep it as simple and minimal as possible.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProgramIni {
 	pub version: String,
 	pub name: String,
@@ -28,6 +35,13 @@ pub struct ProgramIni {
 	pub date: String,
 }
 
+impl ProgramIni {
+	/// The contents of `slot_N.ini`.
+	pub fn to_ini(&self) -> String {
+		serde_ini::to_string(&ProgramIniTopLevel { program: self.clone() }).expect("Serializing slot data")
+	}
+}
+
 pub type Programs = [Option<ProgramIni>; NUM_SLOTS];
 
 fn slot_number_to_ini_name(number: SlotNumber) -> String {
@@ -63,8 +77,8 @@ pub fn get(device: &mut Device, slot: SlotNumber) -> DevResult<Option<ProgramIni
 
 pub fn get_all(device: &mut Device) -> DevResult<Programs> {
 	let mut ret: Programs = [None, None, None, None, None, None, None, None];
-	for slot_num in 0..NUM_SLOTS {
-		ret[slot_num as usize] = get(device, SlotNumber::from_index(slot_num).unwrap())?;
+	for (slot_num, program) in ret.iter_mut().enumerate() {
+		*program = get(device, SlotNumber::from_index(slot_num).unwrap())?;
 	}
 	Ok(ret)
 }
@@ -0,0 +1,163 @@
+//! The streams that PROS programs multiplex over the brain's user port.
+//!
+//! Each packet is a four-byte stream name, such as `sout`, followed by the data, encoded with COBS and terminated by a zero byte.
+//! Standard input goes the other way unframed.
+
+use crate::util::cobs;
+use std::collections::HashMap;
+use std::fmt::{self, Display, Formatter};
+
+/// The name of a stream, such as `sout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct StreamId(pub [u8; 4]);
+
+impl StreamId {
+	/// Standard output.
+	pub const STDOUT: Self = Self(*b"sout");
+	/// Standard error.
+	pub const STDERR: Self = Self(*b"serr");
+	/// Kernel debugging messages.
+	pub const KERNEL_DEBUG: Self = Self(*b"kdbg");
+	/// JSON telemetry.
+	pub const JINX: Self = Self(*b"jinx");
+}
+
+impl Display for StreamId {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		formatter.write_str(&String::from_utf8_lossy(&self.0))
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Packet {
+	pub stream: StreamId,
+	pub data: Vec<u8>,
+}
+
+impl Packet {
+	/// The packet as sent by the brain, including the trailing zero byte.
+	pub fn encode(&self) -> Vec<u8> {
+		let mut ret = cobs::encode(&[&self.stream.0[..], &self.data].concat());
+		ret.push(0);
+		ret
+	}
+}
+
+/// What was received between two zero bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Frame {
+	Packet(Packet),
+	/// Not a valid packet, e.g. output from before the program started or from a program that doesn't use PROS's stream protocol. The bytes are as received.
+	Malformed(Vec<u8>),
+}
+
+impl Frame {
+	fn decode(raw: Vec<u8>) -> Self {
+		match cobs::decode(&raw) {
+			Ok(decoded) if decoded.len() >= 4 => Self::Packet(Packet {
+				stream: StreamId(decoded[..4].try_into().unwrap()),
+				data: decoded[4..].to_vec(),
+			}),
+			_ => Self::Malformed(raw),
+		}
+	}
+}
+
+/// Splits received bytes into frames. Bytes are buffered until the zero byte ending their frame arrives.
+#[derive(Debug, Default)]
+pub struct Decoder {
+	buffer: Vec<u8>,
+}
+
+impl Decoder {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// All the frames that were completed by `data`.
+	pub fn push(&mut self, data: &[u8]) -> Vec<Frame> {
+		let mut ret = Vec::new();
+		for &byte in data {
+			if byte == 0 {
+				// consecutive delimiters are harmless
+				if !self.buffer.is_empty() {
+					ret.push(Frame::decode(std::mem::take(&mut self.buffer)));
+				}
+			} else {
+				self.buffer.push(byte);
+			}
+		}
+		ret
+	}
+	/// The bytes received since the last complete frame.
+	pub fn pending(&self) -> &[u8] {
+		&self.buffer
+	}
+}
+
+/// Reassembles the frames of each stream into lines, since programs are free to print part of a line at a time.
+/// Malformed frames are treated as a stream of their own, with no ID.
+#[derive(Debug, Default)]
+pub struct Lines {
+	partial: HashMap<Option<StreamId>, Vec<u8>>,
+}
+
+impl Lines {
+	pub fn new() -> Self {
+		Self::default()
+	}
+	/// All the lines that were completed by the frame, without their line endings.
+	pub fn push(&mut self, frame: &Frame) -> Vec<(Option<StreamId>, String)> {
+		let (stream, data) = match frame {
+			Frame::Packet(packet) => (Some(packet.stream), &packet.data),
+			Frame::Malformed(data) => (None, data),
+		};
+		let partial = self.partial.entry(stream).or_default();
+		let mut ret = Vec::new();
+		for &byte in data {
+			if byte == b'\n' {
+				let line = std::mem::take(partial);
+				let line = line.strip_suffix(b"\r").unwrap_or(&line);
+				ret.push((stream, String::from_utf8_lossy(line).into_owned()));
+			} else {
+				partial.push(byte);
+			}
+		}
+		ret
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn decode_split_packets() {
+		let stdout = Packet {
+			stream: StreamId::STDOUT,
+			data: b"hello\0world\n".to_vec(),
+		};
+		let stderr = Packet {
+			stream: StreamId::STDERR,
+			data: b"oops\n".to_vec(),
+		};
+		let mut data = stdout.encode();
+		data.extend(stderr.encode());
+		data.extend(b"\xffgarbage\0");
+		let mut decoder = Decoder::new();
+		let (first, second) = data.split_at(7);
+		assert_eq!(decoder.push(first), []);
+		assert_eq!(decoder.pending().len(), 7);
+		assert_eq!(decoder.push(second), [Frame::Packet(stdout), Frame::Packet(stderr), Frame::Malformed(b"\xffgarbage".to_vec())]);
+		assert!(decoder.pending().is_empty());
+	}
+
+	#[test]
+	fn reassemble_lines() {
+		let packet = |stream, data: &[u8]| Frame::Packet(Packet { stream, data: data.to_vec() });
+		let mut lines = Lines::new();
+		assert_eq!(lines.push(&packet(StreamId::STDOUT, b"a=1")), []);
+		assert_eq!(lines.push(&packet(StreamId::STDERR, b"oops\r\n")), [(Some(StreamId::STDERR), "oops".to_owned())]);
+		assert_eq!(lines.push(&packet(StreamId::STDOUT, b", b=2\nc")), [(Some(StreamId::STDOUT), "a=1, b=2".to_owned())]);
+		assert_eq!(lines.push(&Frame::Malformed(b"raw\n".to_vec())), [(None, "raw".to_owned())]);
+	}
+}
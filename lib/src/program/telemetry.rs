@@ -0,0 +1,71 @@
+//! Numeric samples that a program prints for the host to collect, one per line, such as `@tlm left=1.2 right=3.4`.
+
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// A tagged set of named values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sample {
+	/// The word after the `@`, so that programs can print several kinds of samples.
+	pub tag: String,
+	/// The values, in the order they were printed.
+	pub values: Vec<(String, f64)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleFromStrError {
+	/// The line doesn't start with `@`, so it isn't meant to be a sample.
+	NotASample,
+	MissingTag,
+	InvalidField(String),
+}
+
+impl Display for SampleFromStrError {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NotASample => formatter.write_str("the line doesn't start with @"),
+			Self::MissingTag => formatter.write_str("missing tag after @"),
+			Self::InvalidField(field) => write!(formatter, "invalid field {:?}, expected name=number", field),
+		}
+	}
+}
+
+impl std::error::Error for SampleFromStrError {}
+
+impl FromStr for Sample {
+	type Err = SampleFromStrError;
+	fn from_str(line: &str) -> Result<Self, Self::Err> {
+		let line = line.trim().strip_prefix('@').ok_or(SampleFromStrError::NotASample)?;
+		let mut words = line.split_whitespace();
+		let tag = words.next().ok_or(SampleFromStrError::MissingTag)?.to_owned();
+		let values = words
+			.map(|field| match field.split_once('=') {
+				Some((name, value)) if !name.is_empty() => value.parse().map(|value| (name.to_owned(), value)).map_err(|_| SampleFromStrError::InvalidField(field.to_owned())),
+				_ => Err(SampleFromStrError::InvalidField(field.to_owned())),
+			})
+			.collect::<Result<_, _>>()?;
+		Ok(Self { tag, values })
+	}
+}
+
+impl Sample {
+	pub fn get(&self, name: &str) -> Option<f64> {
+		self.values.iter().find(|(value_name, _)| value_name == name).map(|(_, value)| *value)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse() {
+		let sample: Sample = "@tlm left=1.2 right=-3.4e1 ".parse().unwrap();
+		assert_eq!(sample.tag, "tlm");
+		assert_eq!(sample.values, [("left".to_owned(), 1.2), ("right".to_owned(), -34.0)]);
+		assert_eq!(sample.get("right"), Some(-34.0));
+		assert_eq!("hello".parse::<Sample>(), Err(SampleFromStrError::NotASample));
+		assert_eq!("@".parse::<Sample>(), Err(SampleFromStrError::MissingTag));
+		assert_eq!("@tlm left=fast".parse::<Sample>(), Err(SampleFromStrError::InvalidField("left=fast".to_owned())));
+	}
+}
@@ -0,0 +1,76 @@
+//! Recognising the progress of a test run in a program's output, in the format that Rust's built-in test harness prints:
+//!
+//! ```text
+//! running 2 tests
+//! test drive::forward ... ok
+//! test drive::turn ... FAILED
+//! test result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out
+//! ```
+
+/// How a single test went.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestOutcome {
+	Passed,
+	Failed,
+	Ignored,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TestEvent {
+	Started {
+		count: usize,
+	},
+	Finished {
+		name: String,
+		outcome: TestOutcome,
+	},
+	/// The whole run is over.
+	Summary {
+		passed: bool,
+	},
+}
+
+/// The event on this line of output, if any.
+pub fn parse_line(line: &str) -> Option<TestEvent> {
+	let line = line.trim_end();
+	if let Some(rest) = line.strip_prefix("test result: ") {
+		return if rest.starts_with("ok") {
+			Some(TestEvent::Summary { passed: true })
+		} else if rest.starts_with("FAILED") {
+			Some(TestEvent::Summary { passed: false })
+		} else {
+			None
+		};
+	}
+	if let Some(rest) = line.strip_prefix("running ") {
+		let count = rest.strip_suffix(" tests").or_else(|| rest.strip_suffix(" test"))?;
+		return count.parse().ok().map(|count| TestEvent::Started { count });
+	}
+	let (name, outcome) = line.strip_prefix("test ")?.rsplit_once(" ... ")?;
+	let outcome = match outcome {
+		"ok" => TestOutcome::Passed,
+		"FAILED" => TestOutcome::Failed,
+		_ if outcome.starts_with("ignored") => TestOutcome::Ignored,
+		_ => return None,
+	};
+	Some(TestEvent::Finished { name: name.to_owned(), outcome })
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parse() {
+		assert_eq!(parse_line("running 1 test"), Some(TestEvent::Started { count: 1 }));
+		assert_eq!(parse_line("running 12 tests\r"), Some(TestEvent::Started { count: 12 }));
+		let finished = |name: &str, outcome| Some(TestEvent::Finished { name: name.to_owned(), outcome });
+		assert_eq!(parse_line("test drive::forward ... ok"), finished("drive::forward", TestOutcome::Passed));
+		assert_eq!(parse_line("test drive::turn ... FAILED"), finished("drive::turn", TestOutcome::Failed));
+		assert_eq!(parse_line("test slow ... ignored, needs a field"), finished("slow", TestOutcome::Ignored));
+		assert_eq!(parse_line("test result: ok. 2 passed; 0 failed"), Some(TestEvent::Summary { passed: true }));
+		assert_eq!(parse_line("test result: FAILED. 1 passed; 1 failed"), Some(TestEvent::Summary { passed: false }));
+		assert_eq!(parse_line("hello"), None);
+		assert_eq!(parse_line("test output without a result"), None);
+	}
+}
@@ -0,0 +1,160 @@
+//! Uploading a program to a slot, as `slot_N.bin` along with its description in `slot_N.ini`.
+//!
+//! A program can also be split into a cold package, with the libraries and runtime that rarely change, and a hot binary that is linked to it.
+//! The cold package is named after its CRC, as `lib_XXXXXXXX.bin`, and only uploaded if the device doesn't already have it, so that usually only the small hot binary is uploaded.
+//! Slots whose programs have the same cold package share it.
+
+use super::{slot_number_to_bin_qual_file, slot_number_to_ini_qual_file, ProgramIni, Programs, SlotNumber};
+use crate::crc::CrcComputable;
+use crate::device::filesystem::{self as fs, Address, QualFile, TimeStamp, TransferCompleteAction, WriteArgs};
+use crate::device::send::FileMetadataByName;
+use crate::device::{Device, DeviceError as DE, Result as DevResult};
+use std::cmp::Ordering;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+/// Where cold packages are loaded, as PROS expects.
+pub const COLD_ADDRESS: Address = 0x03_80_00_00;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChooseSlotError {
+	NoEmptySlot,
+	/// A program with the same name but a newer version is already uploaded.
+	NewerVersion {
+		slot: SlotNumber,
+		version: String,
+	},
+}
+
+impl Display for ChooseSlotError {
+	fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::NoEmptySlot => formatter.write_str("there are no empty slots"),
+			Self::NewerVersion { slot, version } => write!(formatter, "slot {} already has a newer version ({}) of the program", slot, version),
+		}
+	}
+}
+
+impl std::error::Error for ChooseSlotError {}
+
+/// Compares versions such as `1.2.10` component by component, ignoring anything after a `-` or `+`.
+/// `None` if either version isn't in that format.
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+	fn parse(version: &str) -> Option<Vec<u64>> {
+		let version = version.split(['-', '+']).next()?;
+		version.split('.').map(|component| component.parse().ok()).collect()
+	}
+	Some(parse(a)?.cmp(&parse(b)?))
+}
+
+/// The slot to upload a program to: the slot of an uploaded program with the same name if that program is not newer than this one, otherwise the first empty slot.
+pub fn choose_slot(programs: &Programs, name: &str, version: &str) -> Result<SlotNumber, ChooseSlotError> {
+	let same_name = programs.iter().enumerate().find_map(|(idx, program)| program.as_ref().filter(|program| program.name == name).map(|program| (idx, program)));
+	if let Some((idx, program)) = same_name {
+		let slot = SlotNumber::from_index(idx).unwrap();
+		return match compare_versions(&program.version, version) {
+			Some(Ordering::Greater) => Err(ChooseSlotError::NewerVersion { slot, version: program.version.clone() }),
+			_ => Ok(slot),
+		};
+	}
+	programs.iter().position(Option::is_none).map(|idx| SlotNumber::from_index(idx).unwrap()).ok_or(ChooseSlotError::NoEmptySlot)
+}
+
+/// Extra arguments to `upload` and `upload_hot_cold`.
+#[derive(Default, Clone, Copy)]
+pub struct UploadArgs {
+	/// What to do with the program once it's uploaded.
+	pub action: TransferCompleteAction,
+	/// Gzip the binaries before uploading them. See `WriteArgs::compress`.
+	pub compress: bool,
+}
+
+/// Upload the program to the slot in `ini`, replacing whatever is there.
+/// The description is written first so that the program is complete by the time `args.action` runs it.
+pub fn upload(device: &mut Device, ini: &ProgramIni, binary: &[u8], args: &UploadArgs) -> DevResult<()> {
+	upload_with_link(device, ini, binary, args, None)
+}
+
+/// The name of a cold package, which depends only on its contents, so that it isn't uploaded again for each slot.
+/// Since it's shared, deleting a program along with its linked file also takes the cold package away from other slots that use it.
+pub fn cold_file(cold: &[u8]) -> QualFile {
+	let crc = *<u32 as CrcComputable>::update_crc(&mut 0u32, cold);
+	QualFile::from_str(&format!("lib_{:08x}.bin", crc)).unwrap()
+}
+
+/// Upload a program split into a hot binary and a cold package, linking the former to the latter.
+/// The cold package is skipped if the device already has an identical one at `COLD_ADDRESS`. Returns whether the cold package was uploaded.
+pub fn upload_hot_cold(device: &mut Device, ini: &ProgramIni, hot: &[u8], cold: &[u8], args: &UploadArgs) -> DevResult<bool> {
+	let cold_file = cold_file(cold);
+	// compress it here rather than in `write_file_from_slice` so that the CRC can be compared with what's on the device
+	let compressed;
+	let cold = if args.compress {
+		compressed = fs::gzip(cold);
+		&compressed
+	} else {
+		cold
+	};
+	let up_to_date = is_on_device(device, &cold_file, cold, COLD_ADDRESS)?;
+	if !up_to_date {
+		let cold_args = WriteArgs {
+			address: Some(COLD_ADDRESS),
+			overwrite: true,
+			timestamp: TimeStamp::now(),
+			..Default::default()
+		};
+		device.write_file_from_slice(cold, &cold_file, &cold_args)?;
+	}
+	upload_with_link(device, ini, hot, args, Some(cold_file.common))?;
+	Ok(!up_to_date)
+}
+
+/// Whether the slot already has this binary, as `upload` with the same arguments would have uploaded it, going by the CRC and size of the remote file.
+pub fn is_uploaded(device: &mut Device, slot: SlotNumber, binary: &[u8], args: &UploadArgs) -> DevResult<bool> {
+	let bin_file = slot_number_to_bin_qual_file(slot).map_err(|err| DE::Other(Box::new(err)))?;
+	if args.compress {
+		is_on_device(device, &bin_file, &fs::gzip(binary), fs::DEFAULT_ADDRESS)
+	} else {
+		is_on_device(device, &bin_file, binary, fs::DEFAULT_ADDRESS)
+	}
+}
+
+/// Whether the file on the device has exactly this data at this address.
+fn is_on_device(device: &mut Device, file: &QualFile, data: &[u8], address: Address) -> DevResult<bool> {
+	let crc = *<u32 as CrcComputable>::update_crc(&mut 0u32, data);
+	let existing = device.get_file_metadata_by_name(&FileMetadataByName::new(&file.common))?;
+	Ok(existing.is_some_and(|existing| existing.crc == crc && existing.size as usize == data.len() && existing.address == address))
+}
+
+fn upload_with_link(device: &mut Device, ini: &ProgramIni, binary: &[u8], args: &UploadArgs, linked_file: Option<fs::QualFileName>) -> DevResult<()> {
+	let ini_file = slot_number_to_ini_qual_file(ini.slot).map_err(|err| DE::Other(Box::new(err)))?;
+	let bin_file = slot_number_to_bin_qual_file(ini.slot).map_err(|err| DE::Other(Box::new(err)))?;
+	let ini_args = WriteArgs {
+		overwrite: true,
+		timestamp: TimeStamp::now(),
+		..Default::default()
+	};
+	device.write_file_from_slice(ini.to_ini().as_bytes(), &ini_file, &ini_args)?;
+	let bin_args = WriteArgs {
+		action: args.action,
+		compress: args.compress,
+		address: Some(fs::DEFAULT_ADDRESS),
+		overwrite: true,
+		timestamp: TimeStamp::now(),
+		linked_file,
+		..Default::default()
+	};
+	device.write_file_from_slice(binary, &bin_file, &bin_args)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn versions() {
+		assert_eq!(compare_versions("1.2.10", "1.2.9"), Some(Ordering::Greater));
+		assert_eq!(compare_versions("1.2.0-beta", "1.2.0"), Some(Ordering::Equal));
+		assert_eq!(compare_versions("1.2", "1.2.0"), Some(Ordering::Less));
+		assert_eq!(compare_versions("one", "1"), None);
+	}
+}
@@ -0,0 +1,24 @@
+//! Helpers shared between tests.
+
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+
+/// A `Write`r that can still be read after it has been given away, e.g., to a `Recorder`.
+#[derive(Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+	pub fn contents(&self) -> Vec<u8> {
+		self.0.lock().unwrap().clone()
+	}
+}
+
+impl Write for SharedBuffer {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		self.0.lock().unwrap().extend_from_slice(data);
+		Ok(data.len())
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
@@ -0,0 +1,17 @@
+//! The async counterpart to `Transport`, for use with `AsyncDevice`.
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+/// A bidirectional async byte stream.
+///
+/// Unlike `Transport`, there is no timeout here; `AsyncDevice` applies its own to each read.
+pub trait AsyncTransport: AsyncRead + AsyncWrite + Unpin + Send {
+	/// A human-readable name for the transport, such as the path of a serial port.
+	fn name(&self) -> Option<String>;
+}
+
+impl AsyncTransport for tokio_serial::SerialStream {
+	fn name(&self) -> Option<String> {
+		tokio_serial::SerialPort::name(self)
+	}
+}
@@ -0,0 +1,23 @@
+use std::fmt::{self, Display, Formatter};
+use std::num::ParseIntError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventFromStrError {
+	MissingField(&'static str),
+	InvalidTime,
+	InvalidDirection,
+	InvalidByte(ParseIntError),
+}
+
+impl Display for EventFromStrError {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::MissingField(field) => write!(formatter, "missing {}", field),
+			Self::InvalidTime => formatter.write_str("invalid time; expected a number of seconds"),
+			Self::InvalidDirection => formatter.write_str("invalid direction; expected `tx` or `rx`"),
+			Self::InvalidByte(e) => write!(formatter, "invalid byte: {}", e),
+		}
+	}
+}
+
+impl std::error::Error for EventFromStrError {}
@@ -0,0 +1,94 @@
+use super::EventFromStrError as Error;
+use crate::device::UploadableType;
+use std::fmt::{self, Display, Formatter};
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The direction of data, from the host's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+	/// Sent by the host.
+	Tx,
+	/// Received by the host.
+	Rx,
+}
+
+impl Display for Direction {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		formatter.write_str(match self {
+			Self::Tx => "tx",
+			Self::Rx => "rx",
+		})
+	}
+}
+
+/// Data that passed through the transport in one call to `read` or `write`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+	/// Since recording started.
+	pub time: Duration,
+	pub direction: Direction,
+	pub data: Vec<u8>,
+}
+
+impl Display for Event {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		write!(formatter, "{:.6} {}", self.time.as_secs_f64(), self.direction)?;
+		for byte in &self.data {
+			write!(formatter, " {:02x}", byte)?;
+		}
+		Ok(())
+	}
+}
+
+/// The format is the same as a line of a capture file; see the module documentation.
+impl FromStr for Event {
+	type Err = Error;
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut fields = s.split_whitespace();
+		let time = fields.next().ok_or(Error::MissingField("time"))?;
+		let time = f64::from_str(time).ok().filter(|time| time.is_finite() && *time >= 0.0).ok_or(Error::InvalidTime)?;
+		let direction = match fields.next().ok_or(Error::MissingField("direction"))? {
+			"tx" => Direction::Tx,
+			"rx" => Direction::Rx,
+			_ => return Err(Error::InvalidDirection),
+		};
+		let data = fields.map(|byte| u8::from_str_radix(byte, 16)).collect::<Result<Vec<u8>, _>>().map_err(Error::InvalidByte)?;
+		Ok(Self {
+			time: Duration::from_secs_f64(time),
+			direction,
+			data,
+		})
+	}
+}
+
+/// The prefix of the header line that records the type of device.
+pub(super) const DEVICE_HEADER: &str = "# device:";
+
+/// The contents of a capture file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Capture {
+	/// From the `# device:` header, if there is one.
+	pub device_type: Option<UploadableType>,
+	pub events: Vec<Event>,
+}
+
+/// Read all the events of a capture, skipping blank lines and comments.
+pub fn read_capture(reader: &mut dyn BufRead) -> io::Result<Capture> {
+	let mut ret = Capture { device_type: None, events: Vec::new() };
+	for (line_num, line) in reader.lines().enumerate() {
+		let line = line?;
+		let line = line.trim();
+		let invalid = |e: &dyn Display| io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", line_num + 1, e));
+		if let Some(device_type) = line.strip_prefix(DEVICE_HEADER) {
+			ret.device_type = Some(UploadableType::from_str(device_type.trim()).map_err(|e| invalid(&e))?);
+			continue;
+		}
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+		ret.events.push(Event::from_str(line).map_err(|e| invalid(&e))?);
+	}
+	Ok(ret)
+}
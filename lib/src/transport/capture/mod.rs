@@ -0,0 +1,25 @@
+//! Recording and replaying the traffic of a transport.
+//!
+//! A capture is a text file with one event per line, e.g.:
+//!
+//! ```text
+//! # device: brain
+//! # other lines starting with '#' are comments
+//! 0.000000 tx c9 36 b8 47 a4
+//! 0.001532 rx aa 55 a4 08 01 01 00 00 00 10 00 00
+//! ```
+//!
+//! The first column is the number of seconds since recording started, the second is the direction from the host's point of view, and the rest is the data in hex.
+//! The `# device:` header records the type of device that was captured, so that it can be replayed as the same type. Captures without it are assumed to be of a brain.
+
+mod error;
+mod event;
+mod record;
+mod replay;
+#[cfg(test)]
+mod tests;
+
+pub use error::EventFromStrError;
+pub use event::{read_capture, Capture, Direction, Event};
+pub use record::Recorder;
+pub use replay::Replay;
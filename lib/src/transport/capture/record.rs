@@ -0,0 +1,73 @@
+use super::event::DEVICE_HEADER;
+use super::{Direction, Event};
+use crate::device::{Result, UploadableType};
+use crate::transport::Transport;
+use log::warn;
+use std::io::{self, Read, Write};
+use std::time::{Duration, Instant};
+
+/// A transport that passes everything through to another transport, writing each read and write to a capture.
+pub struct Recorder {
+	underlying: Box<dyn Transport>,
+	capture: Box<dyn Write + Send>,
+	start: Instant,
+}
+
+impl Recorder {
+	/// Writes to `capture` are not buffered by the recorder, so wrap it in a `LineWriter` or `BufWriter` as appropriate.
+	///
+	/// `device_type` is written in the capture's header, for replaying it later.
+	pub fn new(underlying: Box<dyn Transport>, mut capture: Box<dyn Write + Send>, device_type: UploadableType) -> Self {
+		if let Err(e) = writeln!(capture, "{} {}", DEVICE_HEADER, device_type) {
+			warn!("Could not write to capture: {}", e);
+		}
+		Self { underlying, capture, start: Instant::now() }
+	}
+	fn record(&mut self, direction: Direction, data: &[u8]) {
+		let event = Event {
+			time: self.start.elapsed(),
+			direction,
+			data: data.to_vec(),
+		};
+		// a broken capture shouldn't break communication with the device
+		if let Err(e) = writeln!(self.capture, "{}", event) {
+			warn!("Could not write to capture: {}", e);
+		}
+	}
+}
+
+impl Read for Recorder {
+	fn read(&mut self, output: &mut [u8]) -> io::Result<usize> {
+		let ret = self.underlying.read(output)?;
+		if ret > 0 {
+			self.record(Direction::Rx, &output[0..ret]);
+		}
+		Ok(ret)
+	}
+}
+
+impl Write for Recorder {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		let ret = self.underlying.write(data)?;
+		if ret > 0 {
+			self.record(Direction::Tx, &data[0..ret]);
+		}
+		Ok(ret)
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		self.capture.flush()?;
+		self.underlying.flush()
+	}
+}
+
+impl Transport for Recorder {
+	fn name(&self) -> Option<String> {
+		self.underlying.name()
+	}
+	fn timeout(&self) -> Duration {
+		self.underlying.timeout()
+	}
+	fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+		self.underlying.set_timeout(timeout)
+	}
+}
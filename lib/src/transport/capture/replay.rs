@@ -0,0 +1,96 @@
+use super::{Direction, Event};
+use crate::device::{Device, Result};
+use crate::transport::Transport;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+/// A transport that plays back a capture.
+///
+/// Data that is written must match what was sent in the capture, and reads return what was received in the capture.
+/// Reading when the host is expected to send data next, or when the capture is exhausted, times out immediately.
+pub struct Replay {
+	events: VecDeque<Event>,
+	/// How much of the first event has been consumed.
+	offset: usize,
+	/// The total number of bytes consumed, for error messages.
+	position: usize,
+	timeout: Duration,
+}
+
+impl Replay {
+	pub fn new(events: Vec<Event>) -> Self {
+		Self {
+			events: events.into_iter().filter(|event| !event.data.is_empty()).collect(),
+			offset: 0,
+			position: 0,
+			timeout: Device::DEFAULT_TIMEOUT,
+		}
+	}
+	/// Whether everything in the capture has been sent and received.
+	pub fn is_finished(&self) -> bool {
+		self.events.is_empty()
+	}
+	/// The unconsumed part of the next event, if it is in the specified direction.
+	fn next_data(&self, direction: Direction) -> Option<&[u8]> {
+		self.events.front().filter(|event| event.direction == direction).map(|event| &event.data[self.offset..])
+	}
+	fn consume(&mut self, amount: usize) {
+		self.offset += amount;
+		self.position += amount;
+		if self.offset == self.events[0].data.len() {
+			self.events.pop_front();
+			self.offset = 0;
+		}
+	}
+}
+
+impl Read for Replay {
+	fn read(&mut self, output: &mut [u8]) -> io::Result<usize> {
+		if output.is_empty() {
+			return Ok(0);
+		}
+		let data = self.next_data(Direction::Rx).ok_or_else(|| io::Error::new(io::ErrorKind::TimedOut, "Nothing more was received in the capture"))?;
+		let amount = std::cmp::min(data.len(), output.len());
+		output[0..amount].copy_from_slice(&data[0..amount]);
+		self.consume(amount);
+		Ok(amount)
+	}
+}
+
+impl Write for Replay {
+	fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+		let mut written = 0;
+		while written < data.len() {
+			let expected = self
+				.next_data(Direction::Tx)
+				.ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, format!("Data was sent at byte {} of the capture where none was sent originally", self.position)))?;
+			let amount = std::cmp::min(expected.len(), data.len() - written);
+			if expected[0..amount] != data[written..written + amount] {
+				return Err(io::Error::new(
+					io::ErrorKind::InvalidData,
+					format!("Sent data {:02x?} does not match the capture at byte {}, which has {:02x?}", &data[written..written + amount], self.position, &expected[0..amount]),
+				));
+			}
+			self.consume(amount);
+			written += amount;
+		}
+		Ok(written)
+	}
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+impl Transport for Replay {
+	fn name(&self) -> Option<String> {
+		Some("replay".to_owned())
+	}
+	fn timeout(&self) -> Duration {
+		self.timeout
+	}
+	fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+		self.timeout = timeout;
+		Ok(())
+	}
+}
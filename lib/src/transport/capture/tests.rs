@@ -0,0 +1,79 @@
+use super::{read_capture, Direction, Event, Recorder, Replay};
+use crate::device::{filesystem as fs, Device};
+use crate::device::{DeviceError, UploadableType};
+use crate::emulator::VirtualBrain;
+use crate::test_util::SharedBuffer;
+use encde::util::VecWriter;
+use std::str::FromStr;
+use std::time::Duration;
+
+#[test]
+fn event_roundtrip() {
+	let event = Event {
+		time: Duration::from_micros(1532),
+		direction: Direction::Rx,
+		data: vec![0xaa, 0x55, 0x00],
+	};
+	assert_eq!(event.to_string(), "0.001532 rx aa 55 00");
+	assert_eq!(Event::from_str(&event.to_string()).unwrap(), event);
+	assert!(Event::from_str("0.1 sideways 00").is_err());
+}
+
+#[test]
+fn record_then_replay() {
+	let brain = VirtualBrain::default();
+	let file = fs::QualFile::from_str("reveng:capture.bin").unwrap();
+	brain.insert_file(crate::emulator::VirtualFile::new(file.common, file.ty, (0..1000u32).map(|x| x as u8).collect()));
+	let capture = SharedBuffer::default();
+	let recorder = Recorder::new(Box::new(brain), Box::new(capture.clone()), UploadableType::Brain);
+	let mut device = Device::from_transport(UploadableType::Brain, Box::new(recorder)).unwrap();
+	let mut original = VecWriter::new();
+	device.read_file_to_stream(&mut original, &file, &Default::default()).unwrap();
+	drop(device);
+
+	let capture = read_capture(&mut &capture.contents()[..]).unwrap();
+	assert_eq!(capture.device_type, Some(UploadableType::Brain));
+	let mut device = Device::from_transport(UploadableType::Brain, Box::new(Replay::new(capture.events))).unwrap();
+	let mut replayed = VecWriter::new();
+	device.read_file_to_stream(&mut replayed, &file, &Default::default()).unwrap();
+	assert_eq!(replayed.into_inner(), original.into_inner());
+
+	// the capture is finished, so anything else has no response
+	assert!(matches!(device.device_info(), Err(DeviceError::Io(_))));
+}
+
+/// Command 0x22 with no payload, answered with a length too short to hold even the echoed command and the CRC.
+fn garbled_length_exchange() -> Vec<Event> {
+	use crate::crc::CrcComputable;
+	let mut command = Device::COMMAND_HEADER.to_vec();
+	command.extend_from_slice(&[Device::EXT_COMMAND, 0x22, 0x00]);
+	let crc = *0u16.update_crc(&command);
+	command.extend_from_slice(&crc.to_be_bytes());
+	let response = [&Device::RESPONSE_HEADER[..], &[Device::EXT_COMMAND, 0x01, 0x22]].concat();
+	vec![
+		Event {
+			time: Duration::ZERO,
+			direction: Direction::Tx,
+			data: command,
+		},
+		Event {
+			time: Duration::ZERO,
+			direction: Direction::Rx,
+			data: response,
+		},
+	]
+}
+
+#[test]
+fn garbled_ext_length() {
+	use crate::device::ProtocolError;
+	let mut device = Device::from_transport(UploadableType::Brain, Box::new(Replay::new(garbled_length_exchange()))).unwrap();
+	assert!(matches!(device.raw_ext_command(0x22, &[]), Err(DeviceError::Protocol(ProtocolError::BadLength { received_length: 1, .. }))));
+}
+
+#[test]
+fn probe_garbled_ext_length() {
+	use crate::device::probe::Outcome;
+	let mut device = Device::from_transport(UploadableType::Brain, Box::new(Replay::new(garbled_length_exchange()))).unwrap();
+	assert!(matches!(device.probe_ext_command(0x22, &[]).unwrap(), Outcome::Malformed(_)));
+}
@@ -0,0 +1,23 @@
+//! The byte transport that the protocol runs over.
+//!
+//! Normally this is a USB serial port, but anything that can move bytes in both directions and time out can be used, e.g., a pty, a TCP bridge, or a test double.
+
+use crate::device::Result;
+use std::io::{Read, Write};
+use std::time::Duration;
+
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod capture;
+mod serial_port;
+
+/// A bidirectional byte stream with a read timeout.
+///
+/// Reading and writing (including flushing) are done through `Read` and `Write`.
+/// Reads that take longer than the timeout should fail with `std::io::ErrorKind::TimedOut`.
+pub trait Transport: Read + Write + Send {
+	/// A human-readable name for the transport, such as the path of a serial port.
+	fn name(&self) -> Option<String>;
+	fn timeout(&self) -> Duration;
+	fn set_timeout(&mut self, timeout: Duration) -> Result<()>;
+}
@@ -0,0 +1,16 @@
+use super::Transport;
+use crate::device::{DeviceError, Result};
+use serialport::SerialPort;
+use std::time::Duration;
+
+impl Transport for Box<dyn SerialPort> {
+	fn name(&self) -> Option<String> {
+		SerialPort::name(&**self)
+	}
+	fn timeout(&self) -> Duration {
+		SerialPort::timeout(&**self)
+	}
+	fn set_timeout(&mut self, timeout: Duration) -> Result<()> {
+		SerialPort::set_timeout(&mut **self, timeout).map_err(DeviceError::from)
+	}
+}
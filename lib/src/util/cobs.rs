@@ -0,0 +1,85 @@
+//! Consistent overhead byte stuffing, which removes all zero bytes from data so that zero can be used to delimit packets.
+//! PROS uses this for the streams on the brain's user port.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Encode the data without the trailing zero delimiter.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+	let mut ret = Vec::with_capacity(data.len() + data.len() / 254 + 1);
+	// the index of the code byte for the block being built, which is filled in once the block ends
+	let mut code_index = 0;
+	let mut code = 1u8;
+	ret.push(0);
+	for &byte in data {
+		if byte != 0 {
+			ret.push(byte);
+			code += 1;
+		}
+		if byte == 0 || code == 0xff {
+			ret[code_index] = code;
+			code_index = ret.len();
+			code = 1;
+			ret.push(0);
+		}
+	}
+	ret[code_index] = code;
+	ret
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CobsDecodeError {
+	ZeroByte { position: usize },
+	Truncated,
+}
+
+impl Display for CobsDecodeError {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::ZeroByte { position } => write!(formatter, "unexpected zero byte at position {}", position),
+			Self::Truncated => formatter.write_str("data ends in the middle of a block"),
+		}
+	}
+}
+
+impl std::error::Error for CobsDecodeError {}
+
+/// The inverse of `encode`. The data must not include the trailing zero delimiter.
+pub fn decode(data: &[u8]) -> Result<Vec<u8>, CobsDecodeError> {
+	let mut ret = Vec::with_capacity(data.len());
+	let mut position = 0;
+	while position < data.len() {
+		let code = data[position] as usize;
+		if code == 0 {
+			return Err(CobsDecodeError::ZeroByte { position });
+		}
+		let end = position + code;
+		let block = data.get(position + 1..end).ok_or(CobsDecodeError::Truncated)?;
+		if let Some(offset) = block.iter().position(|&byte| byte == 0) {
+			return Err(CobsDecodeError::ZeroByte { position: position + 1 + offset });
+		}
+		ret.extend_from_slice(block);
+		position = end;
+		// a full block is not followed by an implicit zero
+		if code != 0xff && position < data.len() {
+			ret.push(0);
+		}
+	}
+	Ok(ret)
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn roundtrip() {
+		assert_eq!(super::encode(&[]), [0x01]);
+		assert_eq!(super::encode(&[0x00]), [0x01, 0x01]);
+		assert_eq!(super::encode(&[0x11, 0x22, 0x00, 0x33]), [0x03, 0x11, 0x22, 0x02, 0x33]);
+		assert_eq!(super::decode(&[0x03, 0x11, 0x22, 0x02, 0x33]).unwrap(), [0x11, 0x22, 0x00, 0x33]);
+		assert_eq!(super::decode(&[0x03, 0x11]), Err(super::CobsDecodeError::Truncated));
+		assert_eq!(super::decode(&[0x02, 0x00]), Err(super::CobsDecodeError::ZeroByte { position: 1 }));
+		let long: Vec<u8> = (0..600).map(|i| (i % 7) as u8).chain(std::iter::repeat_n(0x55, 300)).collect();
+		let encoded = super::encode(&long);
+		assert!(!encoded.contains(&0));
+		assert_eq!(super::decode(&encoded).unwrap(), long);
+	}
+}
@@ -0,0 +1,50 @@
+//! Hex strings, for showing and entering raw data.
+
+use std::fmt::{self, Display, Formatter};
+
+/// Space-separated pairs of lowercase hex digits.
+pub fn encode(data: &[u8]) -> String {
+	data.iter().map(|byte| format!("{:02x}", byte)).collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HexDecodeError {
+	OddLength,
+	InvalidDigit { position: usize },
+}
+
+impl Display for HexDecodeError {
+	fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+		match self {
+			Self::OddLength => formatter.write_str("odd number of hex digits"),
+			Self::InvalidDigit { position } => write!(formatter, "invalid hex digit at position {}", position),
+		}
+	}
+}
+
+impl std::error::Error for HexDecodeError {}
+
+/// The inverse of `encode`, except that whitespace and colons may appear anywhere, or not at all.
+pub fn decode(s: &str) -> Result<Vec<u8>, HexDecodeError> {
+	let digits = s
+		.char_indices()
+		.filter(|(_, c)| !c.is_whitespace() && *c != ':')
+		.map(|(position, c)| c.to_digit(16).map(|digit| digit as u8).ok_or(HexDecodeError::InvalidDigit { position }))
+		.collect::<Result<Vec<u8>, _>>()?;
+	if digits.len() % 2 != 0 {
+		return Err(HexDecodeError::OddLength);
+	}
+	Ok(digits.chunks(2).map(|pair| (pair[0] << 4) | pair[1]).collect())
+}
+
+#[cfg(test)]
+mod tests {
+	#[test]
+	fn roundtrip() {
+		assert_eq!(super::encode(&[0x01, 0xab]), "01 ab");
+		assert_eq!(super::decode("01 ab").unwrap(), [0x01, 0xab]);
+		assert_eq!(super::decode("01:AB").unwrap(), [0x01, 0xab]);
+		assert_eq!(super::decode("1ab"), Err(super::HexDecodeError::OddLength));
+		assert_eq!(super::decode("0g"), Err(super::HexDecodeError::InvalidDigit { position: 1 }));
+	}
+}
@@ -1,2 +1,4 @@
+pub mod cobs;
+pub mod hex;
 pub mod num;
 pub mod presence;
@@ -44,6 +44,22 @@ impl std::fmt::Display for NotOne {
 impl std::error::Error for NotOne {}
 
 impl Presence {
+	/// All the devices that are present, e.g. to configure them.
+	pub fn devices_mut(&mut self) -> &mut [Device] {
+		match self {
+			Self::None => &mut [],
+			Self::One(item) => std::slice::from_mut(item),
+			Self::Many(items) => items,
+		}
+	}
+	/// All the devices that are present, for commands that work on any number of them.
+	pub fn into_all(self) -> Vec<Device> {
+		match self {
+			Self::None => Vec::new(),
+			Self::One(item) => vec![item],
+			Self::Many(items) => items,
+		}
+	}
 	pub fn as_result(self) -> Result<Device, NotOne> {
 		match self {
 			Self::None => Err(NotOne::None),